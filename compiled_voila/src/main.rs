@@ -7,6 +7,7 @@ fn main() {
         str_to_vec_u8(env!("v_code")).into(), // into() automatically deserializes the data
         std::path::PathBuf::from(env!("v_path")),
         env!("v_recursive").parse().unwrap(),
+        str_to_vec_u8(env!("v_options")).into(),
     ) {
         eprintln!("{}", e);
         std::process::exit(1);
@@ -8,6 +8,7 @@ fn criterion_benchmark(c: &mut Criterion) {
                 black_box("@name == @name { print(@name.file); print(@a, @parent) }".to_string()),
                 black_box(std::path::PathBuf::from(env!("HOME"))),
                 black_box(true),
+                black_box(Default::default()),
             )
         })
     });
@@ -0,0 +1,83 @@
+use super::HasSpan;
+use super::{Lookup, Str, StrComponent};
+use serde_derive::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A name given to an interpolated string for the whole script, which is resolved
+/// for each file wherever the name is used. Bindings can use the ones before them.
+///
+/// # Examples
+///
+/// ```voila
+/// let @dest = /backup/@parent;
+/// @name ~= \.log$ { move(@path, @dest/@name) }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Binding {
+    /// The name, without the at
+    pub name: String,
+    pub value: Vec<StrComponent>,
+    pub span: Range<usize>,
+}
+
+impl HasSpan for Binding {
+    fn span(&self) -> &Range<usize> {
+        &self.span
+    }
+}
+
+/// The components of `sequence` with the bindings that it uses replaced by their values
+pub fn expand_bindings(sequence: &[StrComponent], bindings: &[Binding]) -> Vec<StrComponent> {
+    sequence
+        .iter()
+        .flat_map(|component| match component {
+            StrComponent::Variable(name) => {
+                match bindings.iter().rposition(|binding| &binding.name == name) {
+                    Some(pos) => expand_bindings(&bindings[pos].value, &bindings[..pos]),
+                    None => vec![component.clone()],
+                }
+            },
+            _ => vec![component.clone()],
+        })
+        .collect()
+}
+
+use super::parser::*;
+use super::Token;
+
+impl<'source> Parse<'source> for Binding {
+    // the closure passes on the errors of the parsers it calls, spans included
+    #[allow(clippy::result_large_err)]
+    fn parse(parser: &mut Parser<'source>) -> ParseRes<Self> {
+        parser.with_context(ContextLevel::Binding, |parser| {
+            let start = parser.current_token_span().start;
+            // the `let` keyword
+            parser.accept_current();
+            parser.expect_token(Token::Variable, Some("to name the binding"))?;
+            let name = &parser.current_token_source()[1..];
+            if name.contains('=') {
+                return Err(parser.error(ParseErrorKind::InvalidBinding));
+            }
+            // the files' own variables can't be hidden by a binding
+            match parser.parse::<Lookup>() {
+                Err(e) if matches!(e.kind, ParseErrorKind::UnknownVariable { .. }) => (),
+                _ => return Err(parser.error(ParseErrorKind::BoundLookup(name.to_string()))),
+            }
+            let name = name.to_string();
+            parser.accept_current();
+            parser.expect_token(Token::Identifier, Some("`=` before the value"))?;
+            if parser.current_token_source() != "=" {
+                return Err(parser.error(ParseErrorKind::InvalidBinding));
+            }
+            parser.accept_current();
+            let value: Str = parser.parse()?;
+            parser.expect_token(Token::Semicolon, Some("to end the binding"))?;
+            parser.accept_current();
+            Ok(Self {
+                name,
+                value: value.sequence,
+                span: start..parser.offset(),
+            })
+        })
+    }
+}
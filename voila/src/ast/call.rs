@@ -1,5 +1,6 @@
 use super::parser::{ContextLevel, Parse, ParseErrorKind, ParseRes, Parser, WantedSpec};
 use super::HasSpan;
+use super::Overwrite;
 use super::Str;
 use super::Token;
 use serde_derive::{Deserialize, Serialize};
@@ -26,6 +27,8 @@ pub struct Call<'source> {
     pub function_kind: Function,
     pub arguments: Vec<Str<'source>>,
     pub safe: bool,
+    /// Overwrite policy for this call only, see [`Overwrite`]
+    pub overwrite: Option<Overwrite>,
     span: Range<usize>,
 }
 
@@ -112,6 +115,25 @@ pub enum Function {
     /// # Safety
     /// since `gzd` has an output directory, it may overwrite a lot af files! Use with care.
     GzipDecompress { safe: bool },
+    /// Rename a file in place, with a name built from a template
+    ///
+    /// # Call format
+    /// `rename` receives the file to rename and the template for its new name, which
+    /// can have these placeholders besides the usual variables:
+    /// - `%n`: a counter starting at 1, `%3n` pads it with zeros to 3 digits.
+    /// - `%U` and `%L`: upper/lowercase everything until `%E` or the end.
+    /// - `%Y`, `%m`, `%d`, `%H`, `%M` and `%S`: date and time of the file's last modification.
+    /// - `%%`: a literal `%`.
+    ///
+    /// Renames are done all together once every file has been processed, sorted by their
+    /// path (which is the order the counter follows). Renames into a name that's already
+    /// taken, or that more than one file wants, are skipped, unless the file that has it
+    /// is renamed too. With `--dry-run`, a table with the old and new names is shown instead.
+    ///
+    /// # Safety
+    /// `rename` doesn't overwrite anything, but it changes the name of the file, so
+    /// don't access it in the same cycle.
+    Rename { safe: bool },
     /// Create a file, with optional contents
     ///
     /// # Call format
@@ -130,6 +152,48 @@ pub enum Function {
     /// As safe as the executable is. Like in the shell function, the safety checker will treat
     /// arguments as access, modify and created as has 0 information about what the executable will do
     Child { safe: bool },
+    /// Change the permissions of a file or directory
+    ///
+    /// # Call format
+    /// `chmod` receives the path and the new mode, either in octal (`755`, `0644`) or
+    /// symbolic like the `chmod` command (`u+x`, `go-w`, `a=r`). Symbolic modes can be
+    /// split in several arguments: `chmod(@path, u=rwx, g=rx, o=)`. Outside of unix systems,
+    /// only whether the file is writable is changed.
+    ///
+    /// # Safety
+    /// `chmod` modifies the file's metadata, so don't access the file in the same cycle.
+    Chmod { safe: bool },
+    /// Set the access and modification times of a file, creating it if it doesn't exist
+    ///
+    /// # Call format
+    /// `touch` receives the path and an optional time, either `yyyy-mm-dd`,
+    /// `yyyy-mm-dd hh:mm:ss` (in UTC, like the rest of the timestamps) or seconds since the
+    /// UNIX epoch. It defaults to the current time.
+    ///
+    /// # Safety
+    /// `touch` modifies the file's metadata, so don't access the file in the same cycle.
+    Touch { safe: bool },
+    /// Write a checksum list that coreutils can check, like `sha256sum -c`
+    ///
+    /// # Call format
+    /// `manifest` receives the path of the list and the algorithm of the sums, one of the
+    /// specifiers of `@sum`. Every file that calls it is listed, relative to the list's directory:
+    /// `{ manifest(SHA256SUMS, sha256) }`. Lists are written once every file has been processed,
+    /// and they replace whatever was in their place. They can be checked afterwards with
+    /// `@verify`.
+    Manifest { safe: bool },
+    // calls for some platforms only go last, so the others keep their place in
+    // compiled scripts
+    /// Change the owner and group of a file or directory (unix-only)
+    ///
+    /// # Call format
+    /// `chown` receives the path and the new owner, in the same format as the `chown`
+    /// command: `user`, `user:group` or `:group`, by name or numeric ID.
+    ///
+    /// # Safety
+    /// `chown` modifies the file's metadata, so don't access the file in the same cycle.
+    #[cfg(unix)]
+    Chown { safe: bool },
 }
 
 impl Function {
@@ -137,20 +201,38 @@ impl Function {
         match self {
             Self::Copy { safe: _ }
             | Self::Move { safe: _ }
+            | Self::Rename { safe: _ }
             | Self::GzipCompress { safe: _ }
-            | Self::GzipDecompress { safe: _ } => 2,
+            | Self::GzipDecompress { safe: _ }
+            | Self::Chmod { safe: _ }
+            | Self::Manifest { safe: _ } => 2,
+            #[cfg(unix)]
+            Self::Chown { safe: _ } => 2,
             Self::Delete { safe: _ }
             | Self::Shell { safe: _ }
             | Self::Mkdir { safe: _ }
             | Self::Create { safe: _ }
-            | Self::Child { safe: _ } => 1,
+            | Self::Child { safe: _ }
+            | Self::Touch { safe: _ } => 1,
             Self::Print { safe: _ } => 0,
         }
     }
+    /// Whether the function writes into a destination that may already exist
+    pub const fn overwrites(&self) -> bool {
+        matches!(
+            self,
+            Self::Copy { .. }
+                | Self::Move { .. }
+                | Self::Create { .. }
+                | Self::GzipCompress { .. }
+                | Self::GzipDecompress { .. }
+        )
+    }
     fn from_name(source: &str, safe: bool) -> Option<Self> {
         Some(match source.trim() {
             "copy" => Self::Copy { safe },
             "move" => Self::Move { safe },
+            "rename" => Self::Rename { safe },
             "gzc" => Self::GzipCompress { safe },
             "gzd" => Self::GzipDecompress { safe },
             "delete" => Self::Delete { safe },
@@ -159,6 +241,11 @@ impl Function {
             "print" => Self::Print { safe },
             "create" => Self::Create { safe },
             "child" => Self::Child { safe },
+            "chmod" => Self::Chmod { safe },
+            #[cfg(unix)]
+            "chown" => Self::Chown { safe },
+            "touch" => Self::Touch { safe },
+            "manifest" => Self::Manifest { safe },
             _ => return None,
         })
     }
@@ -170,10 +257,16 @@ impl Function {
             | Function::Delete { safe }
             | Function::Copy { safe }
             | Function::Move { safe }
+            | Function::Rename { safe }
             | Function::GzipCompress { safe }
             | Function::GzipDecompress { safe }
             | Function::Create { safe }
-            | Function::Child { safe } => safe,
+            | Function::Child { safe }
+            | Function::Chmod { safe }
+            | Function::Touch { safe }
+            | Function::Manifest { safe } => safe,
+            #[cfg(unix)]
+            Function::Chown { safe } => safe,
         }
     }
 }
@@ -183,6 +276,7 @@ impl fmt::Display for Function {
         f.write_str(match self {
             Self::Copy { safe: _ } => "copy",
             Self::Move { safe: _ } => "move",
+            Self::Rename { safe: _ } => "rename",
             Self::GzipCompress { safe: _ } => "gzc",
             Self::GzipDecompress { safe: _ } => "gzd",
             Self::Delete { safe: _ } => "delete",
@@ -191,6 +285,11 @@ impl fmt::Display for Function {
             Self::Print { safe: _ } => "print",
             Self::Create { safe: _ } => "create",
             Self::Child { safe: _ } => "child",
+            Self::Chmod { safe: _ } => "chmod",
+            #[cfg(unix)]
+            Self::Chown { safe: _ } => "chown",
+            Self::Touch { safe: _ } => "touch",
+            Self::Manifest { safe: _ } => "manifest",
         })
     }
 }
@@ -229,7 +328,21 @@ impl HasSpan for Call<'_> {
 impl<'source> Parse<'source> for Call<'source> {
     fn parse(parser: &mut Parser<'source>) -> ParseRes<Self> {
         parser.with_context(ContextLevel::Call, |parser| {
-            let function_kind = parser.parse()?;
+            let overwrite = if parser
+                .expect_token(
+                    Token::Identifier,
+                    Some("as the name of the function or a modifier"),
+                )?
+                .starts_with("overwrite=")
+            {
+                Some(parser.parse()?)
+            } else {
+                None
+            };
+            let function_kind: Function = parser.parse()?;
+            if overwrite.is_some() && !function_kind.overwrites() {
+                return Err(parser.error(ParseErrorKind::NoOverwritePolicy(function_kind)));
+            }
             let start = parser.current_token_span().start;
             parser.accept_current();
             parser.expect_token(
@@ -244,6 +357,7 @@ impl<'source> Parse<'source> for Call<'source> {
                     WantedSpec::explicit_multiple(vec![
                         Token::Variable,
                         Token::Identifier,
+                        Token::QuotedString,
                         Token::CloseParen,
                     ])
                     .with_explanation("end of argument list or argument to the function"),
@@ -268,19 +382,25 @@ impl<'source> Parse<'source> for Call<'source> {
                 function_kind,
                 arguments,
                 safe: function_kind.is_safe(),
+                overwrite,
                 span: start..end,
             })
         })
     }
 }
-use crate::interpreter::{Cache, ErrorKind, ExprResult};
+use super::{Lookup, SumKind};
+use crate::interpreter::{Cache, Context, ErrorKind, ExprResult};
 use path_absolutize::*;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-pub fn run_call(call: &Call, cache: Arc<Mutex<Cache>>) -> Result<(), ErrorKind> {
+pub fn run_call(call: &Call, cache: Arc<Mutex<Cache>>, ctx: &Context) -> Result<(), ErrorKind> {
     use crate::interpreter::ArgCountMismatched;
 
+    if ctx.aborted() {
+        return Ok(());
+    }
+
     // note: already considered streaming the arguments instead
     // of collecting all of them, but the number of arguments is very low (1 or 2),
     // so there is no real performance hit if we evaluate all of them now.
@@ -295,13 +415,61 @@ pub fn run_call(call: &Call, cache: Arc<Mutex<Cache>>) -> Result<(), ErrorKind>
         .map(|arg| cache.lock().unwrap().resolve(arg))
         .map(|x| x.map(ExprResult::cast_to_string))
         .collect::<Result<_, _>>()?;
+
+    ArgCountMismatched::check(call.function_kind, args.len())?;
+
+    // manifests are written when the run ends, but the sum of the file is computed now
+    let listed = match call.function_kind {
+        Function::Manifest { .. } => {
+            let kind: SumKind = args[1]
+                .trim()
+                .parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let mut cache = cache.lock().unwrap();
+            let sum = cache.resolve_var(&Lookup::Sum(kind))?.cast_to_string();
+            Some((kind, cache.get_path().clone(), sum))
+        },
+        _ => None,
+    };
     // drop the guard now since we're finished
     drop(cache);
 
-    ArgCountMismatched::check(call.function_kind, args.len())?;
+    // renames are done in a batch when the run ends
+    if let Function::Rename { .. } = call.function_kind {
+        return ctx.renames.queue(&args[0], &args[1]).map_err(Into::into);
+    }
+
+    let overwrite = call.overwrite.unwrap_or(ctx.overwrite);
+    if !overwrite.apply(call.function_kind, &mut args)? {
+        return Ok(());
+    }
+
+    if ctx.dry_run && !matches!(call.function_kind, Function::Print { .. }) {
+        return print(vec![format!(
+            "dry run: {function}({args})",
+            function = call.function_kind,
+            args = args.join(", ")
+        )])
+        .map_err(Into::into);
+    }
+
+    // kept until the call is done, so no prompt is shown while it runs
+    let _turn = match &ctx.prompt {
+        Some(prompt) => match prompt.confirm(call.function_kind, &args)? {
+            Some(turn) => Some(turn),
+            None => return Ok(()),
+        },
+        None => None,
+    };
+
+    // back up what the call may destroy before executing it
+    let entry = match &ctx.journal {
+        Some(journal) => journal.prepare(call.function_kind, &args)?,
+        None => None,
+    };
 
     // todo: error contexts in interpreter errors...
-    match call.function_kind {
+    let res = match call.function_kind {
         Function::Print { safe: _ } => print(args),
         Function::Create { safe: _ } => create(&args[0], args.get(1).map(String::as_str)),
         Function::Mkdir { safe: _ } => mkdir(args),
@@ -310,12 +478,28 @@ pub fn run_call(call: &Call, cache: Arc<Mutex<Cache>>) -> Result<(), ErrorKind>
             copy_file_or_dir(args[0].as_str().into(), args[1].as_str().into())
         },
         Function::Move { safe: _ } => move_file(&args[0], &args[1]),
-        Function::GzipCompress { safe: _ } => gzc(&args[0], &args[1]),
-        Function::GzipDecompress { safe: _ } => gzd(&args[0], &args[1]),
+        Function::GzipCompress { safe: _ } => gzc(&args[0], &args[1], overwrite),
+        Function::GzipDecompress { safe: _ } => gzd(&args[0], &args[1], overwrite),
         Function::Shell { safe: _ } => shell(args),
         Function::Child { safe: _ } => child(&args.remove(0), args),
+        Function::Rename { safe: _ } => unreachable!("renames are queued"),
+        Function::Chmod { safe: _ } => chmod(&args[0], &args[1..]),
+        #[cfg(unix)]
+        Function::Chown { safe: _ } => chown(&args[0], &args[1]),
+        Function::Touch { safe: _ } => touch(&args[0], args.get(1).map(String::as_str)),
+        Function::Manifest { safe: _ } => {
+            let (kind, file, sum) = listed.unwrap();
+            ctx.manifests.queue(&args[0], kind, &file, sum)
+        },
+    };
+
+    if let (Some(journal), Some(entry)) = (&ctx.journal, entry) {
+        match res {
+            Ok(()) => journal.record(entry)?,
+            Err(_) => journal.discard(entry),
+        }
     }
-    .map_err(Into::into)
+    res.map_err(Into::into)
 }
 
 fn print(args: Vec<String>) -> Result<(), io::Error> {
@@ -378,14 +562,7 @@ fn copy_file_or_dir(mut source: PathBuf, mut dest: PathBuf) -> Result<(), io::Er
         dest = dest.absolutize()?.into();
     }
 
-    if dest.exists() && dest.is_dir() {
-        dest = dest.join(
-            source
-                .file_name()
-                .and_then(std::ffi::OsStr::to_str)
-                .unwrap(),
-        );
-    }
+    dest = copy_destination(&source, dest);
 
     if source.is_dir() {
         fs::create_dir_all(dest)?;
@@ -395,35 +572,139 @@ fn copy_file_or_dir(mut source: PathBuf, mut dest: PathBuf) -> Result<(), io::Er
     Ok(())
 }
 
+/// Where `copy` and `move` will put `source`: copying into an existing directory
+/// puts the source inside of it, with the same name
+pub fn copy_destination(source: &std::path::Path, dest: PathBuf) -> PathBuf {
+    if dest.exists() && dest.is_dir() {
+        dest.join(
+            source
+                .file_name()
+                .and_then(std::ffi::OsStr::to_str)
+                .unwrap(),
+        )
+    } else {
+        dest
+    }
+}
+
 fn move_file(source: &str, dest: &str) -> Result<(), io::Error> {
     copy_file_or_dir(source.into(), dest.into())?;
     delete_file_or_dir(source)
 }
 
-fn gzc(source: &str, dest: &str) -> Result<(), io::Error> {
+fn gzc(source: &str, dest: &str, overwrite: Overwrite) -> Result<(), io::Error> {
     use flate2::write::GzEncoder;
     use flate2::Compression;
     use std::fs;
 
-    let dest = fs::File::create(dest)?;
-    let encoder = GzEncoder::new(dest, Compression::default());
-    let mut tar = tar::Builder::new(encoder);
-    let source = PathBuf::from(source);
-    if source.is_dir() {
-        tar.append_dir_all(source.clone(), source)?;
+    // when the archive has to be compared with the existing one,
+    // compress into a temporary file first
+    let compare = overwrite == Overwrite::IfDifferent && std::path::Path::new(dest).exists();
+    let output = if compare {
+        format!("{dest}.voila-tmp")
     } else {
-        tar.append_path(source)?;
+        dest.to_string()
+    };
+
+    let compress = || {
+        let file = fs::File::create(&output)?;
+        let encoder = GzEncoder::new(file, Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        let source = PathBuf::from(source);
+        if source.is_dir() {
+            tar.append_dir_all(source.clone(), source)?;
+        } else {
+            tar.append_path(source)?;
+        }
+        tar.into_inner()?.finish().map(|_| ())
+    };
+
+    match compress() {
+        Ok(()) if compare => settle(
+            output.as_ref(),
+            dest.as_ref(),
+            overwrite,
+            Function::GzipCompress { safe: true },
+        ),
+        Err(e) if compare => {
+            fs::remove_file(&output).unwrap_or(());
+            Err(e)
+        },
+        res => res,
     }
-    tar.finish()
 }
 
-fn gzd(source: &str, dest: &str) -> Result<(), io::Error> {
+fn gzd(source: &str, dest: &str, overwrite: Overwrite) -> Result<(), io::Error> {
     use flate2::read::GzDecoder;
-    use std::fs::File;
+    use std::fs::{self, File};
+    use std::path::{Component, Path};
     use tar::Archive;
 
     let mut archive = Archive::new(GzDecoder::new(File::open(source)?));
-    archive.unpack(dest)
+    if overwrite == Overwrite::Always {
+        return archive.unpack(dest);
+    }
+
+    let dest = Path::new(dest);
+    fs::create_dir_all(dest)?;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        // same protection as `unpack`: never write outside of the destination
+        if path.components().any(|c| {
+            matches!(
+                c,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_)
+            )
+        }) {
+            continue;
+        }
+        let target = dest.join(path);
+        if entry.header().entry_type().is_dir() || !target.exists() {
+            entry.unpack_in(dest)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // extract next to the existing file, so it can be compared with it
+        let extracted = target.with_file_name(format!(
+            "{}.voila-tmp",
+            target.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        entry.unpack(&extracted)?;
+        settle(
+            &extracted,
+            &target,
+            overwrite,
+            Function::GzipDecompress { safe: true },
+        )?;
+    }
+    Ok(())
+}
+
+/// Move a freshly written temporary file to wherever the overwrite policy says,
+/// or discard it if it mustn't be written
+fn settle(
+    temporary: &std::path::Path,
+    dest: &std::path::Path,
+    overwrite: Overwrite,
+    function: Function,
+) -> Result<(), io::Error> {
+    use super::Incoming;
+    use std::fs;
+
+    match overwrite.destination(Incoming::File(temporary), dest) {
+        Ok(Some(target)) => fs::rename(temporary, target),
+        Ok(None) => {
+            overwrite.report_skip(function, dest);
+            fs::remove_file(temporary)
+        },
+        Err(e) => {
+            fs::remove_file(temporary).unwrap_or(());
+            Err(e)
+        },
+    }
 }
 
 use std::process::Command;
@@ -468,3 +749,219 @@ fn child(executable: &str, arguments: Vec<String>) -> Result<(), io::Error> {
         .spawn()
         .map(|_| ())
 }
+
+fn chmod(target: &str, mode: &[String]) -> Result<(), io::Error> {
+    use std::fs;
+
+    let metadata = fs::metadata(target)?;
+    let current = permissions_mode(&metadata);
+    let new = if let [octal] = mode {
+        // `from_str_radix` takes a sign too, but `+644` is a symbolic mode
+        octal
+            .bytes()
+            .all(|b| (b'0'..=b'7').contains(&b))
+            .then(|| u32::from_str_radix(octal, 8).ok())
+            .flatten()
+    } else {
+        None
+    }
+    .filter(|mode| *mode <= 0o7777)
+    .map_or_else(
+        || {
+            mode.iter().try_fold(current, |acc, clause| {
+                symbolic_mode(acc, clause, metadata.is_dir())
+            })
+        },
+        Some,
+    )
+    .ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid mode `{}`", mode.join(",")),
+        )
+    })?;
+    set_permissions_mode(target.as_ref(), new)
+}
+
+/// The permission bits of a file. Outside of unix, only write permissions are known,
+/// so a writable file is `644` and a read only one is `444`
+pub fn permissions_mode(metadata: &std::fs::Metadata) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o7777
+    }
+    #[cfg(not(unix))]
+    {
+        if metadata.permissions().readonly() {
+            0o444
+        } else {
+            0o644
+        }
+    }
+}
+
+pub fn set_permissions_mode(target: &std::path::Path, mode: u32) -> Result<(), io::Error> {
+    use std::fs;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(target, fs::Permissions::from_mode(mode))
+    }
+    #[cfg(not(unix))]
+    {
+        let mut permissions = fs::metadata(target)?.permissions();
+        permissions.set_readonly(mode & 0o222 == 0);
+        fs::set_permissions(target, permissions)
+    }
+}
+
+/// Apply a symbolic mode clause (like `ug+rw` or `o=`) to a mode
+fn symbolic_mode(mode: u32, clause: &str, is_dir: bool) -> Option<u32> {
+    let ops_start = clause.find(['+', '-', '='])?;
+    let (who, mut ops) = clause.split_at(ops_start);
+    // (permission bits, special bits) affected by each class
+    let mut classes = (0, 0);
+    for c in who.chars() {
+        let (perms, special) = match c {
+            'u' => (0o700, 0o4000),
+            'g' => (0o070, 0o2000),
+            'o' => (0o007, 0o1000),
+            'a' => (0o777, 0o7000),
+            _ => return None,
+        };
+        classes = (classes.0 | perms, classes.1 | special);
+    }
+    if classes == (0, 0) {
+        classes = (0o777, 0o7000);
+    }
+
+    let mut mode = mode;
+    while let Some(op) = ops.chars().next() {
+        let perms_end = ops[1..].find(['+', '-', '=']).map_or(ops.len(), |i| i + 1);
+        let mut bits = 0;
+        for c in ops[1..perms_end].chars() {
+            bits |= match c {
+                'r' => 0o444 & classes.0,
+                'w' => 0o222 & classes.0,
+                'x' => 0o111 & classes.0,
+                'X' if is_dir || mode & 0o111 != 0 => 0o111 & classes.0,
+                'X' => 0,
+                's' => 0o6000 & classes.1,
+                't' => 0o1000,
+                _ => return None,
+            };
+        }
+        mode = match op {
+            '+' => mode | bits,
+            '-' => mode & !bits,
+            _ => (mode & !(classes.0 | (classes.1 & 0o6000))) | bits,
+        };
+        ops = &ops[perms_end..];
+    }
+    Some(mode)
+}
+
+#[cfg(unix)]
+fn chown(target: &str, owner: &str) -> Result<(), io::Error> {
+    let (user, group) = owner.split_once(':').unwrap_or((owner, ""));
+    let uid = (!user.is_empty())
+        .then(|| id_from_db(user, "/etc/passwd"))
+        .transpose()?;
+    let gid = (!group.is_empty())
+        .then(|| id_from_db(group, "/etc/group"))
+        .transpose()?;
+    std::os::unix::fs::chown(target, uid, gid)
+}
+
+/// Get a user or group ID, which can be numeric or a name from the
+/// given database (`/etc/passwd` or `/etc/group`). Only those files are read,
+/// so users and groups from other sources of NSS, like LDAP or systemd's
+/// dynamic users, have to be given by their numeric ID
+#[cfg(unix)]
+fn id_from_db(name: &str, db: &str) -> Result<u32, io::Error> {
+    if let Ok(id) = name.parse() {
+        return Ok(id);
+    }
+    // both files have the format `name:password:id:...`
+    std::fs::read_to_string(db)?
+        .lines()
+        .map(|line| line.split(':'))
+        .find_map(|mut fields| {
+            (fields.next() == Some(name))
+                .then(|| fields.nth(1).and_then(|id| id.parse().ok()))
+                .flatten()
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("unknown user or group `{name}`"),
+            )
+        })
+}
+
+/// Get the name of a user or group ID from the given database
+/// (`/etc/passwd` or `/etc/group`), or the ID itself if it has no name there,
+/// like the IDs from other sources of NSS
+#[cfg(unix)]
+pub fn name_from_db(id: u32, db: &str) -> String {
+    std::fs::read_to_string(db)
+        .ok()
+        .and_then(|entries| {
+            entries.lines().find_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                (fields.nth(1)?.parse() == Ok(id)).then(|| name.to_string())
+            })
+        })
+        .unwrap_or_else(|| id.to_string())
+}
+
+fn touch(target: &str, time: Option<&str>) -> Result<(), io::Error> {
+    use filetime::FileTime;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+
+    let time = match time {
+        None => SystemTime::now(),
+        Some(time) => parse_time(time)
+            .and_then(|secs| u64::try_from(secs).ok())
+            .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid time `{time}`"),
+                )
+            })?,
+    };
+    // only a missing file is opened for writing, so read-only files and directories work too
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+    {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => (),
+        res => drop(res?),
+    }
+    let time = FileTime::from_system_time(time);
+    filetime::set_file_times(target, time, time)
+}
+
+/// Seconds since the UNIX epoch of a `yyyy-mm-dd`, `yyyy-mm-dd hh:mm:ss` or numeric time
+fn parse_time(time: &str) -> Option<i64> {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    let time = time.trim();
+    time.parse().ok().or_else(|| {
+        ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|fmt| NaiveDateTime::parse_from_str(time, fmt).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+            })
+            .map(|datetime| datetime.and_utc().timestamp())
+    })
+}
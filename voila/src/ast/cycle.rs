@@ -73,6 +73,7 @@ use std::sync::{mpsc, Arc, Mutex};
 pub fn run_cycle(
     cycle: &Cycle,
     cache: Arc<Mutex<interpreter::Cache>>,
+    ctx: &interpreter::Context,
     pool: &rayon::ThreadPool,
     tx: mpsc::Sender<interpreter::ErrorKind>,
 ) {
@@ -81,7 +82,7 @@ pub fn run_cycle(
             let cache = cache.clone();
             let tx = tx.clone();
             s.spawn(move |_| {
-                if let Err(e) = super::run_call(call, cache) {
+                if let Err(e) = super::run_call(call, cache, ctx) {
                     tx.send(e).unwrap();
                 }
             })
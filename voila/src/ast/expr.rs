@@ -38,6 +38,31 @@ pub enum Expr<'source> {
     },
 }
 
+impl Expr<'_> {
+    /// Whether the expression is just `@content`, maybe through `bindings`
+    fn is_content(&self, bindings: &[super::Binding]) -> bool {
+        match self {
+            Self::Value(val) => {
+                super::expand_bindings(&val.sequence, bindings)
+                    == [super::StrComponent::Lookup(super::Lookup::Content)]
+            },
+            _ => false,
+        }
+    }
+
+    /// The lookups used on both sides of the expression
+    pub fn lookups(&self) -> Vec<super::Lookup> {
+        match self {
+            Self::Value(val) => val.lookups().collect(),
+            Self::Binary { lhs, rhs, .. } => {
+                let mut lookups = lhs.lookups();
+                lookups.extend(rhs.lookups());
+                lookups
+            },
+        }
+    }
+}
+
 impl HasSpan for Expr<'_> {
     fn span(&self) -> &Range<usize> {
         match self {
@@ -63,6 +88,16 @@ impl HasSpan for Expr<'_> {
 /// become true for the moment. There are plans to forbid this in the future with a static
 /// analyzer.
 ///
+/// Relative operators also understand dates and datetimes, which are compared as
+/// points in time, and durations (`30d`, `2h`, `1w 2d`), compared as lengths of time.
+/// `now` is the instant the run started, and it can be shifted with a duration:
+/// `@lastChange < now - 30d`. Sizes can be written with their unit, SI or IEC (`500k`,
+/// `2mb`, `1.5GiB`), and are compared in bytes, as `@size` without a specifier is.
+/// Note that a lone `m` is a duration in minutes, so megabytes have to be written as `mb`.
+///
+/// Matching `@content` alone against a pattern of a file bigger than `--max-content`
+/// reads it line by line instead of failing, so a match there can't span several lines.
+///
 #[derive(Serialize, Deserialize, Debug)]
 pub enum Operator {
     /// `!=`: True if the two sides are strictly not equal.
@@ -164,29 +199,63 @@ fn parse_expr<'source>(
 }
 
 use crate::interpreter::{Cache, ErrorKind, ExprResult, Resolve};
+use std::cmp::Ordering;
+impl Expr<'_> {
+    /// Resolve the expression as a condition, where values on their own (the whole
+    /// condition, or a side of `&&` and `||`) are taken as they are, so `@duplicate`
+    /// is true only when the file is a duplicate
+    pub fn resolve_condition(&self, cache: &mut Cache) -> Result<ExprResult, ErrorKind> {
+        match self {
+            Self::Value(v) => v.resolve_condition(cache),
+            // both sides are always resolved, like in `resolve`
+            Self::Binary {
+                operator: operator @ (Operator::LogicAnd | Operator::LogicOr),
+                lhs,
+                rhs,
+                ..
+            } => {
+                let lhs = lhs.resolve_condition(cache)?.cast_to_bool()?;
+                let rhs = rhs.resolve_condition(cache)?.cast_to_bool()?;
+                Ok(ExprResult::from(match operator {
+                    Operator::LogicAnd => lhs & rhs,
+                    _ => lhs | rhs,
+                }))
+            },
+            _ => self.resolve(cache),
+        }
+    }
+}
+
 impl Resolve for Expr<'_> {
     fn resolve(&self, cache: &mut Cache) -> Result<ExprResult, ErrorKind> {
         match self {
             Self::Value(v) => cache.resolve(v),
+            // the content of files too large to be read whole is searched line by line,
+            // so they can still be matched
+            Self::Binary {
+                operator: operator @ (Operator::Matches | Operator::NMatches),
+                lhs,
+                rhs,
+                ..
+            } if lhs.is_content(&cache.bindings()) && cache.content_too_large() => {
+                let regex = cache.resolve(rhs.as_ref())?.cast_to_regex()?;
+                let found = cache.content_matches(&regex)?;
+                Ok(ExprResult::from(
+                    found == matches!(operator, Operator::Matches),
+                ))
+            },
             Self::Binary {
                 operator, lhs, rhs, ..
             } => {
                 let lhs = cache.resolve(lhs.as_ref())?;
                 let rhs = cache.resolve(rhs.as_ref())?;
+                let now = cache.now();
                 Ok(ExprResult::from(match operator {
                     Operator::Equals => lhs.cast_to_string() == rhs.cast_to_string(),
-                    Operator::GreaterEqual => {
-                        lhs.reinterpret().cast_to_number()? >= rhs.reinterpret().cast_to_number()?
-                    },
-                    Operator::GreaterThan => {
-                        lhs.reinterpret().cast_to_number()? > rhs.reinterpret().cast_to_number()?
-                    },
-                    Operator::LessEqual => {
-                        lhs.reinterpret().cast_to_number()? <= rhs.reinterpret().cast_to_number()?
-                    },
-                    Operator::LessThan => {
-                        lhs.reinterpret().cast_to_number()? < rhs.reinterpret().cast_to_number()?
-                    },
+                    Operator::GreaterEqual => lhs.compare(rhs, now)?.is_some_and(Ordering::is_ge),
+                    Operator::GreaterThan => lhs.compare(rhs, now)?.is_some_and(Ordering::is_gt),
+                    Operator::LessEqual => lhs.compare(rhs, now)?.is_some_and(Ordering::is_le),
+                    Operator::LessThan => lhs.compare(rhs, now)?.is_some_and(Ordering::is_lt),
                     Operator::NEquals => lhs.cast_to_string() != rhs.cast_to_string(),
                     // regex will be always on the right...?
                     // NOTE: now that I think about it, it should be on the left, right?
@@ -196,7 +265,7 @@ impl Resolve for Expr<'_> {
                     // update the description on the README + operator enum before changing
                     // the behavior code.
                     Operator::Matches => rhs.cast_to_regex()?.is_match(&lhs.cast_to_string()),
-                    Operator::NMatches => rhs.cast_to_regex()?.is_match(&lhs.cast_to_string()),
+                    Operator::NMatches => !rhs.cast_to_regex()?.is_match(&lhs.cast_to_string()),
                     // note: using the single ones so a shortcut is not generated,
                     // and the casts are made first. This won't be relevant when types
                     // are validated prior to runtime.
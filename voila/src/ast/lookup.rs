@@ -1,8 +1,7 @@
 use super::parser::{Parse, ParseErrorKind, ParseRes, Parser};
-use crate::interpreter::{Hash, Hasher};
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Lookup {
     /// The file name (basename)
     Name,
@@ -13,30 +12,138 @@ pub enum Lookup {
     /// File owner ID (unix-only)
     #[cfg(unix)]
     OwnerID,
+    /// File owner name, or its ID if it has no name (unix-only)
+    #[cfg(unix)]
+    Owner,
+    /// File group ID (unix-only)
+    #[cfg(unix)]
+    GroupID,
+    /// File group name, or its ID if it has no name (unix-only)
+    #[cfg(unix)]
+    Group,
+    /// Permission bits of the file in octal, like `644` or `4755`
+    Mode,
+    /// Whether the file can be executed
+    Executable,
+    /// Whether the file has the set-user-ID bit (unix-only)
+    #[cfg(unix)]
+    Setuid,
+    /// Inode number of the file (unix-only)
+    #[cfg(unix)]
+    Inode,
+    /// Number of hard links to the file (unix-only)
+    #[cfg(unix)]
+    Nlink,
+    /// Number of 512-byte blocks allocated to the file (unix-only)
+    #[cfg(unix)]
+    Blocks,
     /// Whether the file occupies than 1 byte
     Empty,
     /// Whether the file is read only (for the user that runs this process)
     Readonly,
     /// Whether the file follows the Executable & Linkable Format
     Elf,
-    /// Whether the file is a valid text file
+    /// Whether the file is text: valid UTF-8 without NUL bytes, judging by its start
     Text,
+    /// The media type of the file, like `image/png`, told by its contents
+    Mime,
+    /// The kind of file, like `png`, `elf`, `text` or `data`, told by its contents
+    FileType,
     /// File content
     Content,
+    /// Number of lines of the file, counting the last one even without a trailing newline
+    LineCount,
+    /// Number of words of the file, separated by whitespace
+    WordCount,
+    /// Number of characters of the file, taken as UTF-8
+    CharCount,
+    /// Characters of the longest line of the file
+    LongestLine,
+    /// The encoding the file seems to have: `ascii`, `utf-8`, `utf-16le`, `utf-16be`,
+    /// `binary`, `unknown` or `empty`
+    Encoding,
+    /// Shannon entropy of the bytes of the file, in bits per byte, over the whole file
+    /// or its first KiBs
+    Entropy(Option<usize>),
+    /// Number of NUL bytes of the file
+    NullBytes,
     /// Access to a specific line of the file
     Line(usize),
+    /// The lines of the file in a range, like `10..20`, `10..` or `..20`
+    Lines(LineRange),
+    /// The first lines of the file
+    Head(usize),
+    /// The last lines of the file
+    Tail(usize),
+    /// The lines of the file that match a regular expression
+    Grep(Pattern),
+    /// How many lines of the file match a regular expression
+    GrepCount(Pattern),
     /// Whether the file is hidden
     Hidden,
     /// The file size
     Size(SizeLabel),
     /// A computed sum of the file's contents
     Sum(SumKind),
+    /// The sum recorded for the file in a sidecar (`file.sha256`) or in the manifest of
+    /// its directory (`SHA256SUMS`), empty if there's none
+    SumFile(SumKind),
+    /// Whether the sum of the file is the recorded one: `ok`, `mismatch` or `missing`
+    Verify(SumKind),
     /// time of file creation
     Creation(TimeStamp),
     /// time of the last modification
     LastModification(TimeStamp),
     /// time of the last access to the file
     LastAccess(TimeStamp),
+    /// time of the last change to the file's status (metadata or contents)
+    #[cfg(unix)]
+    Ctime(TimeStamp),
+    /// time since the last modification, as a duration or in the given unit
+    Age(Option<AgeUnit>),
+    /// Whether another file of the run has the same contents and is the original one
+    Duplicate,
+    /// How many other files of the run have the same contents
+    DupCount,
+    /// The path of the file that this one is a copy of, or its own path if it isn't a copy
+    Original,
+    /// Width of the image in pixels, 0 if it isn't an image
+    Width,
+    /// Height of the image in pixels, 0 if it isn't an image
+    Height,
+    /// Format of the image, like `png` or `jpeg`, empty if it isn't an image
+    ImgFormat,
+    /// A field of the EXIF metadata of the image, empty if it doesn't have it
+    Exif(ExifTag),
+    /// Year in which the picture was taken, according to its EXIF metadata
+    ExifYear,
+    /// Month in which the picture was taken, from `01` to `12`
+    ExifMonth,
+    /// Day of the month in which the picture was taken, from `01` to `31`
+    ExifDay,
+    /// The value of an environment variable, empty if it isn't set
+    Env(EnvVar),
+    // the lookups of optional features go last, so the others keep their place in
+    // compiled scripts no matter which features are enabled
+    /// A tag of an audio, video or document file, like its title, empty if it doesn't have it
+    #[cfg(feature = "media")]
+    Tag(MediaTag),
+    /// How long an audio or video file lasts
+    #[cfg(feature = "media")]
+    Duration,
+    /// Pages of a PDF document, 0 if it isn't one
+    #[cfg(feature = "media")]
+    Pages,
+    /// Whether the file is `tracked` by git (and unchanged), `modified`, `untracked` or
+    /// `ignored`, empty if it isn't in a repository
+    #[cfg(feature = "git")]
+    GitStatus,
+    /// Author of the last commit that changed the file
+    #[cfg(feature = "git")]
+    GitAuthor,
+    /// time of the last commit that changed the file
+    #[cfg(feature = "git")]
+    GitLastCommit(TimeStamp),
 }
 
 use Lookup::*;
@@ -48,19 +155,89 @@ impl Lookup {
         "parent",
         #[cfg(unix)]
         "ownerID",
+        #[cfg(unix)]
+        "owner",
+        #[cfg(unix)]
+        "groupID",
+        #[cfg(unix)]
+        "group",
+        "mode",
+        "executable",
+        #[cfg(unix)]
+        "setuid",
+        #[cfg(unix)]
+        "inode",
+        #[cfg(unix)]
+        "nlink",
+        #[cfg(unix)]
+        "blocks",
         "empty",
         "readonly",
         "elf",
         "txt",
+        "mime",
+        "filetype",
         "content",
+        "linecount",
+        "wordcount",
+        "charcount",
+        "longestline",
+        "encoding",
+        "entropy",
+        "nullbytes",
+        "line",
         "lines",
+        "head",
+        "tail",
+        "grep",
+        "grepcount",
         "hidden",
         "size",
         "sum",
         "creation",
         "lastChange",
         "lastAccess",
+        #[cfg(unix)]
+        "ctime",
+        "age",
+        "sumfile",
+        "verify",
+        "duplicate",
+        "dupcount",
+        "original",
+        "width",
+        "height",
+        "imgformat",
+        "exif",
+        "exifyear",
+        "exifmonth",
+        "exifday",
+        "env",
+        #[cfg(feature = "media")]
+        "tag",
+        #[cfg(feature = "media")]
+        "duration",
+        #[cfg(feature = "media")]
+        "pages",
+        #[cfg(feature = "git")]
+        "gitstatus",
+        #[cfg(feature = "git")]
+        "gitauthor",
+        #[cfg(feature = "git")]
+        "gitlastcommit",
     ];
+    /// The variable, out of the lookups and `bindings`, whose name is the closest to
+    /// `name`, if it's close enough to be a typo of it
+    pub fn suggest(name: &str, bindings: &[String]) -> Option<String> {
+        Self::VAR_OPTIONS
+            .iter()
+            .copied()
+            .chain(bindings.iter().map(String::as_str))
+            .map(|option| (strsim::damerau_levenshtein(name, option), option))
+            .filter(|&(distance, option)| distance <= (option.len() / 3).max(1))
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, option)| option.to_string())
+    }
     pub fn as_str<'source>(&self) -> &'source str {
         match self {
             Name => "name",
@@ -68,18 +245,76 @@ impl Lookup {
             Parent => "parent",
             #[cfg(unix)]
             OwnerID => "ownerID",
+            #[cfg(unix)]
+            Owner => "owner",
+            #[cfg(unix)]
+            GroupID => "groupID",
+            #[cfg(unix)]
+            Group => "group",
+            Mode => "mode",
+            Executable => "executable",
+            #[cfg(unix)]
+            Setuid => "setuid",
+            #[cfg(unix)]
+            Inode => "inode",
+            #[cfg(unix)]
+            Nlink => "nlink",
+            #[cfg(unix)]
+            Blocks => "blocks",
             Empty => "empty",
             Readonly => "readonly",
             Elf => "elf",
             Text => "txt",
+            Mime => "mime",
+            FileType => "filetype",
             Content => "content",
-            Line(_) => "lines",
+            LineCount => "linecount",
+            WordCount => "wordcount",
+            CharCount => "charcount",
+            LongestLine => "longestline",
+            Encoding => "encoding",
+            Entropy(_) => "entropy",
+            NullBytes => "nullbytes",
+            Line(_) => "line",
+            Lines(_) => "lines",
+            Head(_) => "head",
+            Tail(_) => "tail",
+            Grep(_) => "grep",
+            GrepCount(_) => "grepcount",
             Hidden => "hidden",
             Size(_) => "size=",
             Sum(_) => "sum=",
             Creation(_) => "creation=",
             LastModification(_) => "lastChange",
             LastAccess(_) => "lastAccess",
+            #[cfg(unix)]
+            Ctime(_) => "ctime",
+            Age(_) => "age",
+            SumFile(_) => "sumfile",
+            Verify(_) => "verify",
+            Duplicate => "duplicate",
+            DupCount => "dupcount",
+            Original => "original",
+            Width => "width",
+            Height => "height",
+            ImgFormat => "imgformat",
+            Exif(_) => "exif",
+            ExifYear => "exifyear",
+            ExifMonth => "exifmonth",
+            ExifDay => "exifday",
+            Env(_) => "env",
+            #[cfg(feature = "media")]
+            Tag(_) => "tag",
+            #[cfg(feature = "media")]
+            Duration => "duration",
+            #[cfg(feature = "media")]
+            Pages => "pages",
+            #[cfg(feature = "git")]
+            GitStatus => "gitstatus",
+            #[cfg(feature = "git")]
+            GitAuthor => "gitauthor",
+            #[cfg(feature = "git")]
+            GitLastCommit(_) => "gitlastcommit",
         }
     }
 }
@@ -93,9 +328,18 @@ impl std::fmt::Display for Lookup {
 impl Parse<'_> for Lookup {
     // this parser assumes that the parser is already at `Token::Variable`
     fn parse(parser: &mut Parser) -> ParseRes<Self> {
+        Self::from_source(parser.current_token_source(), parser.bindings())
+            .map_err(|e| parser.error(e))
+    }
+}
+
+impl Lookup {
+    /// The lookup written as `source`, like `@size=mb`. The `bindings` are only needed
+    /// to suggest a name when it isn't a lookup.
+    pub fn from_source(source: &str, bindings: &[String]) -> Result<Self, ParseErrorKind> {
         use crate::{no_spec, spec};
 
-        let full_var = parser.current_token_source().strip_prefix('@').unwrap();
+        let full_var = source.strip_prefix('@').unwrap();
         let (var_name, var_spec): (&str, Option<&str>) = full_var
             .find('=')
             .map(|idx| {
@@ -103,27 +347,122 @@ impl Parse<'_> for Lookup {
                 (a, Some(&b[1..]))
             })
             .unwrap_or((full_var, None));
+        let var_spec = var_spec.map(unquote_spec);
+        let var_spec = var_spec.as_deref();
         match var_name {
             "name" => no_spec!("name", Name, var_spec),
             "path" => no_spec!("path", Path, var_spec),
             "parent" => no_spec!("parent", Parent, var_spec),
             #[cfg(unix)]
             "ownerID" => no_spec!("ownerID", OwnerID, var_spec),
+            #[cfg(unix)]
+            "owner" => no_spec!("owner", Owner, var_spec),
+            #[cfg(unix)]
+            "groupID" => no_spec!("groupID", GroupID, var_spec),
+            #[cfg(unix)]
+            "group" => no_spec!("group", Group, var_spec),
+            "mode" => no_spec!("mode", Mode, var_spec),
+            "executable" => no_spec!("executable", Executable, var_spec),
+            #[cfg(unix)]
+            "setuid" => no_spec!("setuid", Setuid, var_spec),
+            #[cfg(unix)]
+            "inode" => no_spec!("inode", Inode, var_spec),
+            #[cfg(unix)]
+            "nlink" => no_spec!("nlink", Nlink, var_spec),
+            #[cfg(unix)]
+            "blocks" => no_spec!("blocks", Blocks, var_spec),
             "empty" => no_spec!("empty", Empty, var_spec),
             "readonly" => no_spec!("readonly", Readonly, var_spec),
             "elf" => no_spec!("elf", Elf, var_spec),
             "txt" => no_spec!("txt", Text, var_spec),
+            "mime" => no_spec!("mime", Mime, var_spec),
+            "filetype" => no_spec!("filetype", FileType, var_spec),
             "content" => no_spec!("content", Content, var_spec),
+            "linecount" => no_spec!("linecount", LineCount, var_spec),
+            "wordcount" => no_spec!("wordcount", WordCount, var_spec),
+            "charcount" => no_spec!("charcount", CharCount, var_spec),
+            "longestline" => no_spec!("longestline", LongestLine, var_spec),
+            "encoding" => no_spec!("encoding", Encoding, var_spec),
+            // entropy without a specifier is over the whole file
+            "entropy" if var_spec.is_none() => Ok(Entropy(None)),
+            "entropy" => spec!("entropy", usize, |kib| Entropy(Some(kib)), var_spec),
+            "nullbytes" => no_spec!("nullbytes", NullBytes, var_spec),
             "line" => spec!("line", usize, Line, var_spec),
+            "lines" => spec!("lines", LineRange, Lines, var_spec),
+            "head" => spec!("head", usize, Head, var_spec),
+            "tail" => spec!("tail", usize, Tail, var_spec),
+            "grep" => spec!("grep", Pattern, Grep, var_spec),
+            "grepcount" => spec!("grepcount", Pattern, GrepCount, var_spec),
             "hidden" => no_spec!("hidden", Hidden, var_spec),
+            // sizes without a specifier are in bytes, like size literals (`1.5GiB`)
+            "size" if var_spec.is_none() => Ok(Size(SizeLabel::Bytes)),
             "size" => spec!("size", SizeLabel, Size, var_spec),
             "sum" => spec!("sum", SumKind, Sum, var_spec),
+            // timestamps without a specifier are full datetimes
+            "creation" if var_spec.is_none() => Ok(Creation(TimeStamp::DateTime)),
+            "lastChange" if var_spec.is_none() => Ok(LastModification(TimeStamp::DateTime)),
+            "lastAccess" if var_spec.is_none() => Ok(LastAccess(TimeStamp::DateTime)),
+            #[cfg(unix)]
+            "ctime" if var_spec.is_none() => Ok(Ctime(TimeStamp::DateTime)),
             "creation" => spec!("creation", TimeStamp, Creation, var_spec),
             "lastChange" => spec!("lastChange", TimeStamp, LastModification, var_spec),
             "lastAccess" => spec!("lastAccess", TimeStamp, LastAccess, var_spec),
-            _ => Err(ParseErrorKind::UnknownVariable),
+            #[cfg(unix)]
+            "ctime" => spec!("ctime", TimeStamp, Ctime, var_spec),
+            "age" if var_spec.is_none() => Ok(Age(None)),
+            "age" => spec!("age", AgeUnit, |unit| Age(Some(unit)), var_spec),
+            "sumfile" => spec!("sumfile", SumKind, SumFile, var_spec),
+            "verify" => spec!("verify", SumKind, Verify, var_spec),
+            "duplicate" => no_spec!("duplicate", Duplicate, var_spec),
+            "dupcount" => no_spec!("dupcount", DupCount, var_spec),
+            "original" => no_spec!("original", Original, var_spec),
+            "width" => no_spec!("width", Width, var_spec),
+            "height" => no_spec!("height", Height, var_spec),
+            "imgformat" => no_spec!("imgformat", ImgFormat, var_spec),
+            "exif" => spec!("exif", ExifTag, Exif, var_spec),
+            "exifyear" => no_spec!("exifyear", ExifYear, var_spec),
+            "exifmonth" => no_spec!("exifmonth", ExifMonth, var_spec),
+            "exifday" => no_spec!("exifday", ExifDay, var_spec),
+            "env" => spec!("env", EnvVar, Env, var_spec),
+            #[cfg(feature = "media")]
+            "tag" => spec!("tag", MediaTag, Tag, var_spec),
+            #[cfg(feature = "media")]
+            "duration" => no_spec!("duration", Duration, var_spec),
+            #[cfg(feature = "media")]
+            "pages" => no_spec!("pages", Pages, var_spec),
+            #[cfg(feature = "git")]
+            "gitstatus" => no_spec!("gitstatus", GitStatus, var_spec),
+            #[cfg(feature = "git")]
+            "gitauthor" => no_spec!("gitauthor", GitAuthor, var_spec),
+            #[cfg(feature = "git")]
+            "gitlastcommit" if var_spec.is_none() => Ok(GitLastCommit(TimeStamp::DateTime)),
+            #[cfg(feature = "git")]
+            "gitlastcommit" => spec!("gitlastcommit", TimeStamp, GitLastCommit, var_spec),
+            #[cfg(not(feature = "media"))]
+            "tag" | "duration" | "pages" => Err(ParseErrorKind::MissingFeature {
+                variable: var_name.to_string(),
+                feature: "media",
+            }),
+            #[cfg(not(feature = "git"))]
+            "gitstatus" | "gitauthor" | "gitlastcommit" => Err(ParseErrorKind::MissingFeature {
+                variable: var_name.to_string(),
+                feature: "git",
+            }),
+            _ => Err(ParseErrorKind::UnknownVariable {
+                suggestion: Self::suggest(var_name, bindings),
+            }),
         }
-        .map_err(|e| parser.error(e))
+    }
+}
+
+/// The specifier without its quotes, if it has them. Only the quote that it's in can be
+/// escaped, so the backslashes of patterns like `'\d+\.txt'` are kept.
+fn unquote_spec(spec: &str) -> std::borrow::Cow<'_, str> {
+    match spec.chars().next() {
+        Some(quote @ ('"' | '\'')) => spec[1..spec.len() - 1]
+            .replace(&format!("\\{quote}"), &quote.to_string())
+            .into(),
+        _ => spec.into(),
     }
 }
 
@@ -139,18 +478,31 @@ pub enum SizeLabel {
     GigaBytes,
     MegaBytes,
     KiloBytes,
+    TebiBytes,
+    GibiBytes,
+    MebiBytes,
+    KibiBytes,
     Bytes,
+    /// Human-readable text in the most appropriate binary unit, like `1.50 GiB`
+    Auto,
 }
 
-impl Specifier<Self, 5> for SizeLabel {
-    const OPTS: [&'static str; 5] = ["tb", "gb", "mb", "kb", "bs"];
+impl Specifier<Self, 10> for SizeLabel {
+    const OPTS: [&'static str; 10] = [
+        "tb", "gb", "mb", "kb", "tib", "gib", "mib", "kib", "bs", "auto",
+    ];
     fn detect(source: &str) -> Option<Self> {
         Some(match source {
             "tb" => Self::TeraBytes,
             "gb" => Self::GigaBytes,
             "mb" => Self::MegaBytes,
             "kb" => Self::KiloBytes,
+            "tib" => Self::TebiBytes,
+            "gib" => Self::GibiBytes,
+            "mib" => Self::MebiBytes,
+            "kib" => Self::KibiBytes,
             "bs" => Self::Bytes,
+            "auto" => Self::Auto,
             _ => return None,
         })
     }
@@ -163,7 +515,12 @@ impl std::fmt::Display for SizeLabel {
             Self::GigaBytes => "gb",
             Self::MegaBytes => "mb",
             Self::KiloBytes => "kb",
+            Self::TebiBytes => "tib",
+            Self::GibiBytes => "gib",
+            Self::MebiBytes => "mib",
+            Self::KibiBytes => "kib",
             Self::Bytes => "bs",
+            Self::Auto => "auto",
         })
     }
 }
@@ -176,10 +533,19 @@ pub enum SumKind {
     Sha256,
     Sha384,
     Sha512,
+    /// SHA3-256
+    Sha3,
+    Blake3,
+    /// 64-bit XXH3, a fast non-cryptographic hash
+    Xxh3,
+    /// CRC-32 with the Castagnoli polynomial
+    Crc32c,
 }
 
-impl Specifier<Self, 6> for SumKind {
-    const OPTS: [&'static str; 6] = ["md5", "sha224", "sha256", "sha384", "sha512", "sha1"];
+impl Specifier<Self, 10> for SumKind {
+    const OPTS: [&'static str; 10] = [
+        "md5", "sha224", "sha256", "sha384", "sha512", "sha1", "sha3", "blake3", "xxh3", "crc32c",
+    ];
     fn detect(source: &str) -> Option<Self> {
         Some(match source {
             "md5" => Self::Md5,
@@ -188,11 +554,27 @@ impl Specifier<Self, 6> for SumKind {
             "sha256" => Self::Sha256,
             "sha384" => Self::Sha384,
             "sha512" => Self::Sha512,
+            "sha3" => Self::Sha3,
+            "blake3" => Self::Blake3,
+            "xxh3" => Self::Xxh3,
+            "crc32c" => Self::Crc32c,
             _ => return None,
         })
     }
 }
 
+impl std::str::FromStr for SumKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::detect(s).ok_or_else(|| {
+            format!(
+                "unknown sum `{s}`, expected one of {opts}",
+                opts = Self::OPTS.join(", ")
+            )
+        })
+    }
+}
+
 impl std::fmt::Display for SumKind {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.write_str(match self {
@@ -202,24 +584,55 @@ impl std::fmt::Display for SumKind {
             Self::Sha256 => "sha256",
             Self::Sha384 => "sha384",
             Self::Sha512 => "sha512",
+            Self::Sha3 => "sha3",
+            Self::Blake3 => "blake3",
+            Self::Xxh3 => "xxh3",
+            Self::Crc32c => "crc32c",
         })
     }
 }
 
+/// How a point in time is presented. Plain specifiers are in UTC, while
+/// the ones prefixed with `local` use the timezone of the system.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TimeStamp {
     /// Presented to the user as yyyy-mm-dd
     Date,
     /// Presented to the user as hh:mm:ss
     Hour,
+    /// Presented to the user as yyyy-mm-dd hh:mm:ss
+    DateTime,
+    LocalDate,
+    LocalHour,
+    LocalDateTime,
 }
 
-impl Specifier<Self, 2> for TimeStamp {
-    const OPTS: [&'static str; 2] = ["date", "hour"];
+impl TimeStamp {
+    fn is_local(&self) -> bool {
+        matches!(
+            self,
+            Self::LocalDate | Self::LocalHour | Self::LocalDateTime
+        )
+    }
+}
+
+impl Specifier<Self, 6> for TimeStamp {
+    const OPTS: [&'static str; 6] = [
+        "date",
+        "hour",
+        "datetime",
+        "localdate",
+        "localhour",
+        "localdatetime",
+    ];
     fn detect(source: &str) -> Option<Self> {
         Some(match source {
             "date" => Self::Date,
             "hour" => Self::Hour,
+            "datetime" => Self::DateTime,
+            "localdate" => Self::LocalDate,
+            "localhour" => Self::LocalHour,
+            "localdatetime" => Self::LocalDateTime,
             _ => return None,
         })
     }
@@ -230,6 +643,46 @@ impl std::fmt::Display for TimeStamp {
         f.write_str(match self {
             Self::Date => "date",
             Self::Hour => "hour",
+            Self::DateTime => "datetime",
+            Self::LocalDate => "localdate",
+            Self::LocalHour => "localhour",
+            Self::LocalDateTime => "localdatetime",
+        })
+    }
+}
+
+/// Unit in which `@age` is measured
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AgeUnit {
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+    Weeks,
+}
+
+impl Specifier<Self, 5> for AgeUnit {
+    const OPTS: [&'static str; 5] = ["seconds", "minutes", "hours", "days", "weeks"];
+    fn detect(source: &str) -> Option<Self> {
+        Some(match source {
+            "seconds" => Self::Seconds,
+            "minutes" => Self::Minutes,
+            "hours" => Self::Hours,
+            "days" => Self::Days,
+            "weeks" => Self::Weeks,
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for AgeUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Seconds => "seconds",
+            Self::Minutes => "minutes",
+            Self::Hours => "hours",
+            Self::Days => "days",
+            Self::Weeks => "weeks",
         })
     }
 }
@@ -241,7 +694,203 @@ impl Specifier<Self, 1> for usize {
     }
 }
 
-use crate::interpreter::{with_blocks, Cache, CachedResolve, ErrorKind, ExprResult, Resolve};
+/// EXIF fields that `@exif` can read
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExifTag {
+    DateTimeOriginal,
+    DateTimeDigitized,
+    DateTime,
+    Make,
+    Model,
+    LensModel,
+    Orientation,
+    FNumber,
+    ExposureTime,
+    PhotographicSensitivity,
+    FocalLength,
+    Flash,
+    Artist,
+    Copyright,
+    Software,
+    ImageDescription,
+    GPSLatitude,
+    GPSLongitude,
+    GPSAltitude,
+}
+
+impl ExifTag {
+    pub const fn tag(self) -> exif::Tag {
+        match self {
+            Self::DateTimeOriginal => exif::Tag::DateTimeOriginal,
+            Self::DateTimeDigitized => exif::Tag::DateTimeDigitized,
+            Self::DateTime => exif::Tag::DateTime,
+            Self::Make => exif::Tag::Make,
+            Self::Model => exif::Tag::Model,
+            Self::LensModel => exif::Tag::LensModel,
+            Self::Orientation => exif::Tag::Orientation,
+            Self::FNumber => exif::Tag::FNumber,
+            Self::ExposureTime => exif::Tag::ExposureTime,
+            Self::PhotographicSensitivity => exif::Tag::PhotographicSensitivity,
+            Self::FocalLength => exif::Tag::FocalLength,
+            Self::Flash => exif::Tag::Flash,
+            Self::Artist => exif::Tag::Artist,
+            Self::Copyright => exif::Tag::Copyright,
+            Self::Software => exif::Tag::Software,
+            Self::ImageDescription => exif::Tag::ImageDescription,
+            Self::GPSLatitude => exif::Tag::GPSLatitude,
+            Self::GPSLongitude => exif::Tag::GPSLongitude,
+            Self::GPSAltitude => exif::Tag::GPSAltitude,
+        }
+    }
+}
+
+impl Specifier<Self, 19> for ExifTag {
+    const OPTS: [&'static str; 19] = [
+        "DateTimeOriginal",
+        "DateTimeDigitized",
+        "DateTime",
+        "Make",
+        "Model",
+        "LensModel",
+        "Orientation",
+        "FNumber",
+        "ExposureTime",
+        "PhotographicSensitivity",
+        "FocalLength",
+        "Flash",
+        "Artist",
+        "Copyright",
+        "Software",
+        "ImageDescription",
+        "GPSLatitude",
+        "GPSLongitude",
+        "GPSAltitude",
+    ];
+    fn detect(source: &str) -> Option<Self> {
+        Some(match source {
+            "DateTimeOriginal" => Self::DateTimeOriginal,
+            "DateTimeDigitized" => Self::DateTimeDigitized,
+            "DateTime" => Self::DateTime,
+            "Make" => Self::Make,
+            "Model" => Self::Model,
+            "LensModel" => Self::LensModel,
+            "Orientation" => Self::Orientation,
+            "FNumber" => Self::FNumber,
+            "ExposureTime" => Self::ExposureTime,
+            "PhotographicSensitivity" => Self::PhotographicSensitivity,
+            "FocalLength" => Self::FocalLength,
+            "Flash" => Self::Flash,
+            "Artist" => Self::Artist,
+            "Copyright" => Self::Copyright,
+            "Software" => Self::Software,
+            "ImageDescription" => Self::ImageDescription,
+            "GPSLatitude" => Self::GPSLatitude,
+            "GPSLongitude" => Self::GPSLongitude,
+            "GPSAltitude" => Self::GPSAltitude,
+            _ => return None,
+        })
+    }
+}
+
+/// Tags that `@tag` can read from audio, video and document files
+#[cfg(feature = "media")]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaTag {
+    Title,
+    Artist,
+    Album,
+    Year,
+    Genre,
+    Track,
+    Author,
+}
+
+#[cfg(feature = "media")]
+impl Specifier<Self, 7> for MediaTag {
+    const OPTS: [&'static str; 7] = [
+        "title", "artist", "album", "year", "genre", "track", "author",
+    ];
+    fn detect(source: &str) -> Option<Self> {
+        Some(match source {
+            "title" => Self::Title,
+            "artist" => Self::Artist,
+            "album" => Self::Album,
+            "year" => Self::Year,
+            "genre" => Self::Genre,
+            "track" => Self::Track,
+            "author" => Self::Author,
+            _ => return None,
+        })
+    }
+}
+
+/// Lines from `start` to `end`, both included and counting from 1
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineRange {
+    pub start: usize,
+    /// Without an end, the range goes up to the last line
+    pub end: Option<usize>,
+}
+
+impl Specifier<Self, 3> for LineRange {
+    const OPTS: [&'static str; 3] = ["<start>..<end>", "<start>..", "..<end>"];
+    fn detect(source: &str) -> Option<Self> {
+        let (start, end) = source.split_once("..")?;
+        let start = match start {
+            "" => 1,
+            start => start.parse().ok().filter(|&start| start > 0)?,
+        };
+        let end = match end {
+            "" => None,
+            end => Some(end.parse().ok().filter(|&end| end >= start)?),
+        };
+        Some(Self { start, end })
+    }
+}
+
+/// A regular expression given as a specifier, compiled once for the whole run
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pattern(pub String);
+
+impl Specifier<Self, 1> for Pattern {
+    const OPTS: [&'static str; 1] = ["any regular expression, quoted unless it's a word"];
+    fn detect(source: &str) -> Option<Self> {
+        regex::Regex::new(source).ok()?;
+        Some(Self(source.to_string()))
+    }
+}
+
+/// The name of an environment variable given as a specifier
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnvVar(std::sync::Arc<str>);
+
+impl Specifier<Self, 1> for EnvVar {
+    const OPTS: [&'static str; 1] = ["the name of an environment variable"];
+    fn detect(source: &str) -> Option<Self> {
+        Some(Self(source.into()))
+    }
+}
+
+impl serde::Serialize for EnvVar {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for EnvVar {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| Self(name.into()))
+    }
+}
+
+#[cfg(unix)]
+use super::name_from_db;
+use super::permissions_mode;
+use crate::interpreter::{
+    date_taken, exif_value, expected_sum, grep, grep_count, identify, image_size, is_text,
+    line_range, tail, with_blocks, Cache, CachedResolve, ContentTooLarge, ErrorKind, ExprResult,
+    Resolve, ELF,
+};
 use std::time::SystemTime;
 impl CachedResolve for Lookup {
     fn cached_resolve(&self, cache: &mut Cache) -> Result<ExprResult, ErrorKind> {
@@ -273,6 +922,69 @@ impl CachedResolve for Lookup {
                 .get_file_metadata()
                 .map(|m| m.uid() as f64)
                 .map(ExprResult::from),
+            #[cfg(unix)]
+            Owner => cache
+                .get_file_metadata()
+                .map(|m| name_from_db(m.uid(), "/etc/passwd"))
+                .map(ExprResult::from),
+            #[cfg(unix)]
+            GroupID => cache
+                .get_file_metadata()
+                .map(|m| m.gid() as f64)
+                .map(ExprResult::from),
+            #[cfg(unix)]
+            Group => cache
+                .get_file_metadata()
+                .map(|m| name_from_db(m.gid(), "/etc/group"))
+                .map(ExprResult::from),
+            Mode => cache
+                .get_file_metadata()
+                .map(|m| format!("{:o}", permissions_mode(m)))
+                .map(ExprResult::from),
+            Executable => {
+                #[cfg(unix)]
+                {
+                    cache
+                        .get_file_metadata()
+                        .map(|m| permissions_mode(m) & 0o111 != 0)
+                        .map(ExprResult::from)
+                }
+                #[cfg(not(unix))]
+                {
+                    // there are no permission bits to check, so go by the extension
+                    Ok(ExprResult::from(
+                        cache
+                            .get_path()
+                            .extension()
+                            .and_then(std::ffi::OsStr::to_str)
+                            .map_or(false, |ext| {
+                                ["exe", "bat", "cmd", "com"]
+                                    .iter()
+                                    .any(|x| ext.eq_ignore_ascii_case(x))
+                            }),
+                    ))
+                }
+            },
+            #[cfg(unix)]
+            Setuid => cache
+                .get_file_metadata()
+                .map(|m| m.mode() & 0o4000 != 0)
+                .map(ExprResult::from),
+            #[cfg(unix)]
+            Inode => cache
+                .get_file_metadata()
+                .map(|m| m.ino() as f64)
+                .map(ExprResult::from),
+            #[cfg(unix)]
+            Nlink => cache
+                .get_file_metadata()
+                .map(|m| m.nlink() as f64)
+                .map(ExprResult::from),
+            #[cfg(unix)]
+            Blocks => cache
+                .get_file_metadata()
+                .map(|m| m.blocks() as f64)
+                .map(ExprResult::from),
             Empty => cache
                 .get_file_metadata()
                 .map(|m| m.len() <= 1)
@@ -281,53 +993,49 @@ impl CachedResolve for Lookup {
                 .get_file_metadata()
                 .map(|m| m.permissions().readonly())
                 .map(ExprResult::from),
-            Elf => {
-                use std::io::Read;
-                use std::io::Seek;
-                let mut rb = [0u8; 4];
-                let br = cache.get_file_mut()?;
-                // impl a partial reader that can seek wherever
-                br.rewind().map_err(ErrorKind::from)?;
-                let size = br.read(&mut rb).map_err(ErrorKind::from)?;
-                Ok(size >= 4 && rb == [0x7f, b'E', b'L', b'F']).map(ExprResult::from)
-            },
-            Text => {
-                use std::io::Read;
-                use std::io::Seek;
-                use std::io::SeekFrom;
-
-                let bufreader = cache.get_file_mut()?;
-                bufreader.seek(SeekFrom::End(-1))?;
-                let mut buf = [0u8; 1];
-                let last = if bufreader.read(&mut buf)? > 0 {
-                    Some(buf[0])
-                } else {
-                    None
-                };
-                Ok(last == Some(b'\n') || last == Some(b'\r')).map(ExprResult::from)
-            },
+            Elf => Ok(ExprResult::from(identify(cache.get_sample()?) == ELF)),
+            Text => Ok(ExprResult::from(is_text(cache.get_sample()?))),
+            Mime => Ok(identify(cache.get_sample()?).mime.into()),
+            FileType => Ok(identify(cache.get_sample()?).name.into()),
             Content => {
+                use std::io::Read;
                 use std::ops::Deref;
 
-                let mut reader = cache.get_file_mut()?;
+                let max = cache.max_content();
+                let mut reader = cache
+                    .get_file_mut()?
+                    .take(max.map_or(u64::MAX, |max| max + 1));
                 let mut buffer = Vec::new();
 
                 with_blocks(&mut reader, |block| buffer.extend_from_slice(block)).unwrap_or(());
+                if let Some(max) = max.filter(|&max| buffer.len() as u64 > max) {
+                    return Err(ContentTooLarge::new(cache.get_path().clone(), max).into());
+                }
 
                 Ok(String::from_utf8_lossy(&buffer).deref().into())
             },
-            Line(l) => {
-                use std::io::BufRead;
-
-                let reader = cache.get_file_mut().unwrap();
-                let offset = vec![Ok(String::from(""))].into_iter();
-
-                Ok(offset
-                    .chain(reader.lines())
-                    .nth(*l)
-                    .unwrap_or_else(|| Ok(String::new()))
-                    .unwrap_or_default()
-                    .into())
+            LineCount | WordCount | CharCount | LongestLine | Encoding => {
+                cache.compute_text_stats(self)
+            },
+            Entropy(_) | NullBytes => cache.compute_byte_counts(self),
+            // lines count from 1, so there's nothing at 0
+            Line(0) => Ok(ExprResult::from("")),
+            Line(l) => Ok(line_range(cache.get_file_mut()?, *l, Some(*l))?.into()),
+            Lines(LineRange { start, end }) => {
+                Ok(line_range(cache.get_file_mut()?, *start, *end)?.into())
+            },
+            Head(0) => Ok(ExprResult::from("")),
+            Head(count) => Ok(line_range(cache.get_file_mut()?, 1, Some(*count))?.into()),
+            Tail(count) => Ok(tail(cache.get_file_mut()?, *count)?.into()),
+            Grep(pattern) => {
+                let regex = cache.regex(pattern);
+                Ok(grep(cache.get_file_mut()?, &regex)?.into())
+            },
+            GrepCount(pattern) => {
+                let regex = cache.regex(pattern);
+                Ok(ExprResult::from(
+                    grep_count(cache.get_file_mut()?, &regex)? as f64
+                ))
             },
             Hidden => {
                 #[cfg(not(any(unix, windows)))]
@@ -364,11 +1072,48 @@ impl CachedResolve for Lookup {
                 Ok(get_timestamp(mod_time, ts))
             },
             LastAccess(ts) => {
-                let last_access_time = cache.get_file_metadata()?.created()?;
+                let last_access_time = cache.get_file_metadata()?.accessed()?;
                 Ok(get_timestamp(last_access_time, ts))
             },
+            #[cfg(unix)]
+            Ctime(ts) => {
+                use std::os::unix::fs::MetadataExt;
+                use std::time::Duration;
+
+                let metadata = cache.get_file_metadata()?;
+                let seconds = Duration::from_secs(metadata.ctime().unsigned_abs());
+                let change_time = if metadata.ctime() < 0 {
+                    SystemTime::UNIX_EPOCH - seconds
+                } else {
+                    SystemTime::UNIX_EPOCH + seconds
+                } + Duration::from_nanos(metadata.ctime_nsec() as u64);
+                Ok(get_timestamp(change_time, ts))
+            },
+            Age(unit) => {
+                let modified =
+                    chrono::DateTime::<chrono::Utc>::from(cache.get_file_metadata()?.modified()?);
+                let age = cache.now() - modified.naive_utc();
+                Ok(match unit {
+                    None => ExprResult::Duration(age),
+                    Some(unit) => ExprResult::from(
+                        age.num_milliseconds() as f64
+                            / match unit {
+                                AgeUnit::Seconds => 1_000.0,
+                                AgeUnit::Minutes => 60_000.0,
+                                AgeUnit::Hours => 3_600_000.0,
+                                AgeUnit::Days => 86_400_000.0,
+                                AgeUnit::Weeks => 604_800_000.0,
+                            },
+                    ),
+                })
+            },
             // note: think about using Decimal (for the 2 decimal imposed precision):
             // https://crates.io/crates/rust-decimal
+            Size(SizeLabel::Auto) => Ok(ExprResult::from(
+                byte_unit::Byte::from_bytes(cache.get_file_metadata()?.len() as u128)
+                    .get_appropriate_unit(true)
+                    .to_string(),
+            )),
             Size(sz) => Ok(cache.get_file_metadata()?.len() as f64
                 / match sz {
                     SizeLabel::Bytes => 1.0,
@@ -376,38 +1121,135 @@ impl CachedResolve for Lookup {
                     SizeLabel::MegaBytes => 1_000_000.0,
                     SizeLabel::GigaBytes => 1_000_000_000.0,
                     SizeLabel::TeraBytes => 1_000_000_000_000.0,
+                    SizeLabel::KibiBytes => 1_024.0,
+                    SizeLabel::MebiBytes => 1_048_576.0,
+                    SizeLabel::GibiBytes => 1_073_741_824.0,
+                    SizeLabel::TebiBytes => 1_099_511_627_776.0,
+                    SizeLabel::Auto => unreachable!(),
                 })
             .map(ExprResult::from),
-            Sum(sum) => {
-                let hasher = Hasher::select_from_sum(*sum);
-                hasher
-                    .hash_reader(cache.get_file_mut()?)
-                    .map(ExprResult::from)
-                    .map_err(ErrorKind::from)
+            Sum(sum) => cache.compute_sums(*sum),
+            SumFile(sum) => Ok(expected_sum(cache.get_path(), *sum)?
+                .unwrap_or_default()
+                .into()),
+            Verify(sum) => {
+                let expected = cache.resolve_var(&SumFile(*sum))?.cast_to_string();
+                Ok(if expected.is_empty() {
+                    "missing"
+                } else if cache.resolve_var(&Sum(*sum))?.cast_to_string() == expected {
+                    "ok"
+                } else {
+                    "mismatch"
+                }
+                .into())
+            },
+            Duplicate => Ok(cache
+                .duplicates()
+                .is_some_and(|duplicates| duplicates.is_duplicate(cache.get_path()))
+                .into()),
+            DupCount => Ok(ExprResult::from(
+                cache
+                    .duplicates()
+                    .map_or(0, |duplicates| duplicates.count(cache.get_path()))
+                    as f64,
+            )),
+            Original => Ok(cache
+                .duplicates()
+                .map_or(cache.get_path().as_path(), |duplicates| {
+                    duplicates.original(cache.get_path())
+                })
+                .to_string_lossy()
+                .into_owned()
+                .into()),
+            Width | Height => {
+                let (width, height) = image_size(cache.get_file_mut()?).unwrap_or_default();
+                Ok(ExprResult::from(
+                    if *self == Width { width } else { height } as f64,
+                ))
             },
+            ImgFormat => {
+                let kind = identify(cache.get_sample()?);
+                Ok(if kind.mime.starts_with("image/") {
+                    kind.name
+                } else {
+                    ""
+                }
+                .into())
+            },
+            Exif(tag) => Ok(cache
+                .get_exif()?
+                .map_or_else(|| "".into(), |exif| exif_value(exif, tag.tag()))),
+            ExifYear | ExifMonth | ExifDay => {
+                let format = match self {
+                    ExifYear => "%Y",
+                    ExifMonth => "%m",
+                    _ => "%d",
+                };
+                Ok(cache
+                    .get_exif()?
+                    .and_then(date_taken)
+                    .map(|date| date.format(format).to_string())
+                    .unwrap_or_default()
+                    .into())
+            },
+            Env(var) => Ok(std::env::var(&*var.0).unwrap_or_default().into()),
+            #[cfg(feature = "media")]
+            Tag(tag) => Ok(cache
+                .get_media()?
+                .tags
+                .get(tag)
+                .cloned()
+                .unwrap_or_default()
+                .into()),
+            #[cfg(feature = "media")]
+            Duration => Ok(cache.get_media()?.duration.map_or_else(
+                || "".into(),
+                |duration| {
+                    ExprResult::Duration(
+                        chrono::Duration::from_std(duration)
+                            .unwrap_or_else(|_| chrono::Duration::zero()),
+                    )
+                },
+            )),
+            #[cfg(feature = "media")]
+            Pages => Ok(ExprResult::from(
+                cache.get_media()?.pages.unwrap_or_default() as f64,
+            )),
+            #[cfg(feature = "git")]
+            GitStatus => Ok(cache
+                .git_repo()
+                .map_or("", |repo| repo.status(cache.get_path()))
+                .into()),
+            #[cfg(feature = "git")]
+            GitAuthor => Ok(cache
+                .git_repo()
+                .and_then(|repo| repo.last_commit(cache.get_path()))
+                .map(|commit| commit.author)
+                .unwrap_or_default()
+                .into()),
+            #[cfg(feature = "git")]
+            GitLastCommit(ts) => Ok(cache
+                .git_repo()
+                .and_then(|repo| repo.last_commit(cache.get_path()))
+                .map_or_else(|| "".into(), |commit| get_timestamp(commit.time, ts))),
         }
     }
 }
 impl Resolve for Lookup {
     fn resolve(&self, cache: &mut Cache) -> Result<ExprResult, ErrorKind> {
-        cache.resolve_var(*self)
+        cache.resolve_var(self)
     }
 }
 
-use std::time::Duration;
-fn get_naive_datetime(duration: Duration) -> chrono::NaiveDateTime {
-    let duration = chrono::Duration::from_std(duration).unwrap();
-    let unix = chrono::NaiveDateTime::from_timestamp(0, 0);
-    unix + duration
-}
-
 fn get_timestamp(time: SystemTime, timestamp: &TimeStamp) -> ExprResult {
-    let datetime = time
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .map(get_naive_datetime)
-        .unwrap();
+    let datetime = if timestamp.is_local() {
+        chrono::DateTime::<chrono::Local>::from(time).naive_local()
+    } else {
+        chrono::DateTime::<chrono::Utc>::from(time).naive_utc()
+    };
     match timestamp {
-        TimeStamp::Date => ExprResult::Date(datetime.date()),
-        TimeStamp::Hour => ExprResult::Time(datetime.time()),
+        TimeStamp::Date | TimeStamp::LocalDate => ExprResult::Date(datetime.date()),
+        TimeStamp::Hour | TimeStamp::LocalHour => ExprResult::Time(datetime.time()),
+        TimeStamp::DateTime | TimeStamp::LocalDateTime => ExprResult::DateTime(datetime),
     }
 }
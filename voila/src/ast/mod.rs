@@ -10,15 +10,28 @@ pub trait HasSpan {
 
 mod_use! {
     use script;
+    use binding;
     use target;
     use expr;
     use cycle;
     use call;
+    use overwrite;
     use lookup;
     use string;
 }
 
-pub fn parse_script(source: &str) -> parser::ParseRes<Script> {
+/// Parse the script, knowing the bindings given from outside of it from the start.
+/// The warnings found while parsing come along with it.
+pub fn parse_script<'source>(
+    source: &'source str,
+    defines: &[(String, String)],
+    lenient: bool,
+) -> parser::ParseRes<(Script<'source>, Vec<parser::ParseWarning>)> {
     use parser::Parse;
-    Script::parse_source(source)
+    let mut parser = parser::Parser::new(source).lenient(lenient);
+    for (name, _) in defines {
+        parser.bind(name);
+    }
+    let script = Script::parse(&mut parser)?;
+    Ok((script, parser.take_warnings()))
 }
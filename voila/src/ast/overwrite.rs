@@ -0,0 +1,198 @@
+use super::parser::{Parse, ParseErrorKind, ParseRes, Parser};
+use super::{copy_destination, Function};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What to do when a call is about to write over something that already exists.
+/// It applies to `copy`, `move`, `create`, `gzc` and `gzd`; the rest of functions
+/// don't write into a destination.
+///
+/// # Syntax
+/// The policy can be set for the whole run with `--overwrite <policy>`, or for a single
+/// call by putting `overwrite=<policy>` before the function name, which takes
+/// precedence over the global one:
+/// ```voila
+/// @name ~= #.*\.log# { overwrite=if-newer copy(@path, /backups/logs) }
+/// ```
+///
+/// Every write that is skipped because of the policy is reported.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overwrite {
+    /// `never`: leave whatever is in the destination alone
+    Never,
+    /// `always`: write over the destination
+    #[default]
+    Always,
+    /// `if-newer`: only write over the destination if the source was modified later
+    IfNewer,
+    /// `if-different`: only write over the destination if its size or its contents
+    /// (compared by their hash) are different
+    IfDifferent,
+    /// `rename-new`: keep the destination and write next to it with a numbered name,
+    /// like `file-1.txt`
+    RenameNew,
+}
+
+impl Overwrite {
+    pub const OPTS: [&'static str; 5] =
+        ["never", "always", "if-newer", "if-different", "rename-new"];
+    fn detect(source: &str) -> Option<Self> {
+        Some(match source {
+            "never" => Self::Never,
+            "always" => Self::Always,
+            "if-newer" => Self::IfNewer,
+            "if-different" => Self::IfDifferent,
+            "rename-new" => Self::RenameNew,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Overwrite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Never => "never",
+            Self::Always => "always",
+            Self::IfNewer => "if-newer",
+            Self::IfDifferent => "if-different",
+            Self::RenameNew => "rename-new",
+        })
+    }
+}
+
+impl std::str::FromStr for Overwrite {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::detect(s).ok_or_else(|| format!("unknown overwrite policy `{s}`"))
+    }
+}
+
+impl Parse<'_> for Overwrite {
+    // this parser assumes that the parser is already at a `overwrite=...` identifier
+    fn parse(parser: &mut Parser) -> ParseRes<Self> {
+        let policy = parser
+            .current_token_source()
+            .strip_prefix("overwrite=")
+            .and_then(Self::detect)
+            .ok_or_else(|| {
+                parser.error(ParseErrorKind::InvalidSpecifier {
+                    variable: "overwrite",
+                    options: &Self::OPTS,
+                })
+            })?;
+        parser.accept_current();
+        Ok(policy)
+    }
+}
+
+/// What is going to be written into the destination
+pub enum Incoming<'a> {
+    File(&'a Path),
+    Content(&'a [u8]),
+}
+
+impl Overwrite {
+    /// Decide where the call has to write, given its resolved arguments. The
+    /// destination argument is replaced if the policy renames it. Returns `false`
+    /// if the call has to be skipped.
+    pub fn apply(&self, function: Function, args: &mut [String]) -> io::Result<bool> {
+        let (incoming, dest_idx, dest) = match function {
+            Function::Copy { .. } | Function::Move { .. } => {
+                let source = Path::new(&args[0]);
+                (
+                    Incoming::File(source),
+                    1,
+                    copy_destination(source, args[1].as_str().into()),
+                )
+            },
+            Function::Create { .. } => (
+                Incoming::Content(args.get(1).map_or(&[], |c| c.as_bytes())),
+                0,
+                args[0].as_str().into(),
+            ),
+            // the archive's contents aren't known until it's compressed,
+            // so `gzc` compares them by itself
+            Function::GzipCompress { .. } if *self != Self::IfDifferent => (
+                Incoming::File(Path::new(&args[0])),
+                1,
+                args[1].as_str().into(),
+            ),
+            // `gzd` applies the policy to every file it extracts
+            _ => return Ok(true),
+        };
+        match self.destination(incoming, &dest)? {
+            Some(new) => {
+                if new != dest {
+                    args[dest_idx] = new.to_string_lossy().into_owned();
+                }
+                Ok(true)
+            },
+            None => {
+                self.report_skip(function, &dest);
+                Ok(false)
+            },
+        }
+    }
+
+    /// Where to write `incoming`, or `None` if it shouldn't be written
+    pub fn destination(&self, incoming: Incoming, dest: &Path) -> io::Result<Option<PathBuf>> {
+        let dest_meta = match fs::metadata(dest) {
+            Ok(meta) => meta,
+            Err(_) => return Ok(Some(dest.to_path_buf())),
+        };
+        let overwrite = match (self, incoming) {
+            (Self::Never, _) => false,
+            (Self::Always, _) => true,
+            (Self::IfNewer, Incoming::File(source)) => {
+                fs::metadata(source)?.modified()? > dest_meta.modified()?
+            },
+            // contents that are being created right now are always the newest
+            (Self::IfNewer, Incoming::Content(_)) => true,
+            (Self::IfDifferent, Incoming::File(source)) => {
+                let source_meta = fs::metadata(source)?;
+                // directories are merged, so there's nothing to compare
+                source_meta.is_dir()
+                    || dest_meta.is_dir()
+                    || source_meta.len() != dest_meta.len()
+                    || digest(&mut fs::File::open(source)?)? != digest(&mut fs::File::open(dest)?)?
+            },
+            (Self::IfDifferent, Incoming::Content(mut content)) => {
+                content.len() as u64 != dest_meta.len()
+                    || digest(&mut content)? != digest(&mut fs::File::open(dest)?)?
+            },
+            (Self::RenameNew, _) => return Ok(Some(numbered(dest))),
+        };
+        Ok(overwrite.then(|| dest.to_path_buf()))
+    }
+
+    pub fn report_skip(&self, function: Function, dest: &Path) {
+        eprintln!(
+            "skipped `{function}` into {dest}: it already exists (overwrite policy `{policy}`)",
+            dest = dest.display(),
+            policy = self
+        );
+    }
+}
+
+fn digest<R: io::Read>(reader: &mut R) -> io::Result<String> {
+    use crate::ast::SumKind;
+    use crate::interpreter::{Hash, Hasher};
+
+    Hasher::select_from_sum(SumKind::Sha256).hash_reader(reader)
+}
+
+/// First free `<stem>-<n>.<extension>` next to `dest`
+fn numbered(dest: &Path) -> PathBuf {
+    let stem = dest.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = dest
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| dest.with_file_name(format!("{stem}-{n}{extension}")))
+        .find(|path| fs::symlink_metadata(path).is_err())
+        .unwrap()
+}
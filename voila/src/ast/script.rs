@@ -1,36 +1,157 @@
-use super::Target;
+use super::{Binding, Function, Lookup, Pattern, StrComponent, SumKind, Target};
 use serde_derive::{Deserialize, Serialize};
 
 // The script doesn't have a span, since it represents the **entire** script.
 /// The whole voila script to execute, with a bunch of [Target]s
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Script<'source> {
+    pub bindings: Vec<Binding>,
     pub targets: Vec<Target<'source>>,
 }
 
+impl Script<'_> {
+    /// Every lookup used in the script, both in the conditions and in the calls
+    pub fn lookups(&self) -> Vec<Lookup> {
+        let mut lookups = Vec::new();
+        for binding in &self.bindings {
+            lookups.extend(
+                binding
+                    .value
+                    .iter()
+                    .filter_map(|component| match component {
+                        StrComponent::Lookup(lookup) => Some(lookup.clone()),
+                        _ => None,
+                    }),
+            );
+        }
+        for target in &self.targets {
+            if let Some(condition) = &target.condition {
+                lookups.extend(condition.lookups());
+            }
+            for call in target.cycles.iter().flat_map(|cycle| &cycle.calls) {
+                lookups.extend(call.arguments.iter().flat_map(|arg| arg.lookups()));
+            }
+        }
+        lookups
+    }
+
+    /// Bind `@name` to `value`, in place of the binding of the script with that name if
+    /// it has one, so a script can be given other values without changing it
+    pub fn define(&mut self, name: &str, value: &str) {
+        let value = vec![StrComponent::Literal(value.to_string())];
+        match self
+            .bindings
+            .iter_mut()
+            .find(|binding| binding.name == name)
+        {
+            Some(binding) => binding.value = value,
+            None => self.bindings.insert(
+                0,
+                Binding {
+                    name: name.to_string(),
+                    value,
+                    span: 0..0,
+                },
+            ),
+        }
+    }
+
+    /// Whether the script needs to know which files are duplicates of others
+    pub fn finds_duplicates(&self) -> bool {
+        self.lookups().into_iter().any(|lookup| {
+            matches!(
+                lookup,
+                Lookup::Duplicate | Lookup::DupCount | Lookup::Original
+            )
+        })
+    }
+
+    /// The patterns of the lookups of the script, without repetitions
+    pub fn patterns(&self) -> Vec<Pattern> {
+        let mut patterns = Vec::new();
+        for pattern in self
+            .lookups()
+            .into_iter()
+            .filter_map(|lookup| match lookup {
+                Lookup::Grep(pattern) | Lookup::GrepCount(pattern) => Some(pattern),
+                _ => None,
+            })
+        {
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+        patterns
+    }
+
+    /// The sums used in the script, without repetitions
+    pub fn sums(&self) -> Vec<SumKind> {
+        let manifests = self
+            .targets
+            .iter()
+            .flat_map(|target| &target.cycles)
+            .flat_map(|cycle| &cycle.calls)
+            .filter(|call| matches!(call.function_kind, Function::Manifest { .. }))
+            // only the algorithms that are known before running
+            .filter_map(|call| match call.arguments.get(1)?.sequence.as_slice() {
+                [StrComponent::Literal(algo)] => algo.trim().parse().ok(),
+                _ => None,
+            });
+        let mut sums = Vec::new();
+        for kind in self
+            .lookups()
+            .into_iter()
+            .filter_map(|lookup| match lookup {
+                Lookup::Sum(kind) | Lookup::Verify(kind) => Some(kind),
+                _ => None,
+            })
+            .chain(manifests)
+        {
+            if !sums.contains(&kind) {
+                sums.push(kind);
+            }
+        }
+        sums
+    }
+}
+
 use super::parser::*;
 
 impl<'source> Parse<'source> for Script<'source> {
     fn parse(parser: &mut Parser<'source>) -> ParseRes<Self> {
-        parser.many_eof().map(|targets| Self { targets })
+        let mut script = Self {
+            bindings: Vec::new(),
+            targets: Vec::new(),
+        };
+        while let Some(token) = parser.current_token()? {
+            if token == Token::Identifier && parser.current_token_source() == "let" {
+                let binding: Binding = parser.parse()?;
+                parser.bind(&binding.name);
+                script.bindings.push(binding);
+            } else {
+                script.targets.push(parser.parse()?);
+            }
+        }
+        Ok(script)
     }
 }
 
-use crate::interpreter::{Cache, ErrorKind};
+use crate::interpreter::{Cache, Context, ErrorKind};
 use std::sync::{mpsc, Arc, Mutex};
 pub fn run_script(
     script: &Script,
     path: std::path::PathBuf,
+    ctx: &Context,
     pool: &rayon::ThreadPool,
     tx: mpsc::Sender<ErrorKind>,
 ) {
-    let cache = Arc::new(Mutex::new(Cache::new(path)));
+    let cache = Arc::new(Mutex::new(Cache::new(path, ctx)));
     pool.scope(move |s| {
         for target in &script.targets {
             let tx = tx.clone();
             let cache = cache.clone();
             s.spawn(move |_| {
-                if let Err(e) = super::run_target(target, cache, pool, tx.clone()) {
+                if let Err(e) = super::run_target(target, cache, ctx, pool, tx.clone()) {
                     tx.send(e).unwrap();
                 }
             })
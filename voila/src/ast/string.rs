@@ -1,5 +1,5 @@
 use super::HasSpan;
-use super::Lookup;
+use super::{Binding, Lookup};
 use crate::parser::{ContextLevel, Parse, ParseErrorKind, ParseRes, Parser, Token};
 use serde_derive::{Deserialize, Serialize};
 use std::marker::PhantomData;
@@ -27,9 +27,21 @@ impl HasSpan for Str<'_> {
 pub enum StrComponent {
     Literal(String),
     Lookup(Lookup),
+    /// A variable that isn't a lookup, by its name without the at. It takes the value of
+    /// the binding with that name, or is left as it was written if there's none.
+    Variable(String),
 }
 
 impl<'source> Str<'source> {
+    /// The lookups interpolated in the string
+    pub fn lookups(&self) -> impl Iterator<Item = Lookup> + '_ {
+        self.sequence
+            .iter()
+            .filter_map(|component| match component {
+                StrComponent::Lookup(lookup) => Some(lookup.clone()),
+                StrComponent::Literal(_) | StrComponent::Variable(_) => None,
+            })
+    }
     fn new(first_component: StrComponent, component_span: Range<usize>) -> Self {
         Self {
             sequence: vec![first_component],
@@ -37,6 +49,25 @@ impl<'source> Str<'source> {
             phantom: PhantomData,
         }
     }
+    /// Extend the interpolation with the components of a token, one after another
+    fn extend_all<I: IntoIterator<Item = StrComponent>>(
+        &mut self,
+        components: I,
+        mut last_component_span: Range<usize>,
+        component_span: Range<usize>,
+        full_input: &'source str,
+    ) -> Range<usize> {
+        let mut components = components.into_iter();
+        if let Some(component) = components.next() {
+            last_component_span =
+                self.extend(component, last_component_span, component_span, full_input);
+        }
+        for component in components {
+            let end = last_component_span.end;
+            last_component_span = self.extend(component, last_component_span, end..end, full_input);
+        }
+        last_component_span
+    }
     /// Extend the interpolation with a component, returning the new component span (might be
     /// modified)
     fn extend(
@@ -46,36 +77,31 @@ impl<'source> Str<'source> {
         mut component_span: Range<usize>,
         full_input: &'source str,
     ) -> Range<usize> {
+        // the spaces in between are kept as they were written
+        let between = &full_input[last_component_span.end..component_span.start];
         match component {
-            StrComponent::Literal(_) => {
-                if matches!(self.sequence.last().unwrap(), StrComponent::Literal(_)) {
-                    // if the last component was a literal,
-                    // we can just extend the source.
+            StrComponent::Literal(text) => {
+                if let Some(StrComponent::Literal(last)) = self.sequence.last_mut() {
+                    // if the last component was a literal, we can just extend it
                     component_span.start = last_component_span.start;
-                    let last_ref = self.sequence.last_mut().unwrap();
-                    *last_ref =
-                        StrComponent::Literal(full_input[component_span.clone()].to_string());
+                    last.push_str(between);
+                    last.push_str(&text);
                 } else {
-                    // if the last component wasa variable, we will extend the span to accomodate
-                    // the space in between
+                    // if the last component was a variable, the literal takes the space in
+                    // between
                     component_span.start = last_component_span.end;
-                    self.sequence.push(StrComponent::Literal(
-                        full_input[component_span.clone()].to_string(),
-                    ));
+                    self.sequence
+                        .push(StrComponent::Literal(format!("{between}{text}")));
                 }
             },
-            StrComponent::Lookup(_) => {
-                if matches!(self.sequence.last().unwrap(), StrComponent::Literal(_)) {
-                    // if the last component was a literal, we can extend its source to accomodate
-                    // the spece in between
-                    let last_component_span = last_component_span.start..component_span.start;
-                    let last_ref = self.sequence.last_mut().unwrap();
-                    *last_ref = StrComponent::Literal(full_input[last_component_span].to_string());
+            StrComponent::Lookup(_) | StrComponent::Variable(_) => {
+                if let Some(StrComponent::Literal(last)) = self.sequence.last_mut() {
+                    // if the last component was a literal, it takes the space in between
+                    last.push_str(between);
                 } else {
                     // otherwise, we will put the spaces as a literal into the sequence
-                    self.sequence.push(StrComponent::Literal(
-                        full_input[last_component_span.end..component_span.start].to_string(),
-                    ));
+                    self.sequence
+                        .push(StrComponent::Literal(between.to_string()));
                 }
                 // now we can safely push the lookup, since we already handled the space before it
                 self.sequence.push(component);
@@ -85,45 +111,128 @@ impl<'source> Str<'source> {
     }
 }
 
+/// The component for the variable written as `source` at `span`: a lookup, or else a binding.
+/// The parser makes the error, with where it is.
+fn variable(
+    parser: &mut Parser,
+    source: &str,
+    span: Range<usize>,
+) -> Result<StrComponent, ParseErrorKind> {
+    match Lookup::from_source(source, parser.bindings()) {
+        Ok(lookup) => Ok(StrComponent::Lookup(lookup)),
+        Err(ParseErrorKind::UnknownVariable { suggestion }) => {
+            let name = &source[1..];
+            if parser.bindings().iter().any(|binding| binding == name) {
+                Ok(StrComponent::Variable(name.to_string()))
+            } else if parser.is_strict() {
+                Err(ParseErrorKind::UnknownVariable { suggestion })
+            } else {
+                // every binding is known by now, `--define` ones included, so
+                // without strict mode it can only be text
+                parser.warn(ParseErrorKind::TextVariable { suggestion }, span);
+                Ok(StrComponent::Literal(source.to_string()))
+            }
+        },
+        // other errors are not caught though
+        Err(e) => Err(e),
+    }
+}
+
+/// How long the variable at the start of `source` is, like the lexer reads it outside
+/// of quotes, except that its specifier can't be quoted. It's 1 if there's only the at.
+fn variable_len(source: &str) -> usize {
+    let name_len = source[1..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(source.len() - 1);
+    let rest = &source[1 + name_len..];
+    let spec_len = match rest.strip_prefix('=') {
+        Some(spec) if name_len > 0 => {
+            let word_len = |s: &str| {
+                s.find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(s.len())
+            };
+            let start = word_len(spec);
+            match spec[start..].strip_prefix("..") {
+                Some(end) if start > 0 || word_len(end) > 0 => start + 2 + word_len(end),
+                _ => start,
+            }
+        },
+        _ => 0,
+    };
+    1 + name_len + if spec_len > 0 { 1 + spec_len } else { 0 }
+}
+
+/// The components of the quoted string that the parser is at: its text, with the escapes
+/// replaced, and the variables in it
+fn quoted(parser: &mut Parser) -> Result<Vec<StrComponent>, ParseErrorKind> {
+    let source = parser.current_token_source();
+    let start = parser.current_token_span().start + 1;
+    // without the quotes
+    let source = &source[1..source.len() - 1];
+    let mut components = Vec::new();
+    let mut literal = String::new();
+    let mut chars = source.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => literal.push('\n'),
+                Some('t') => literal.push('\t'),
+                Some('r') => literal.push('\r'),
+                Some(c @ ('"' | '\'' | '\\' | '@')) => literal.push(c),
+                // other backslashes are kept, like in patterns (`'\.txt$'`) or paths
+                Some(c) => {
+                    literal.push('\\');
+                    literal.push(c);
+                },
+                // the lexer doesn't let the closing quote be escaped
+                None => unreachable!(),
+            },
+            '@' if source[idx + 1..].starts_with('@') => {
+                chars.next();
+                literal.push('@');
+            },
+            '@' => match variable_len(&source[idx..]) {
+                // an at on its own can't be a variable
+                1 => literal.push('@'),
+                len => {
+                    if !literal.is_empty() {
+                        components.push(StrComponent::Literal(std::mem::take(&mut literal)));
+                    }
+                    let span = start + idx..start + idx + len;
+                    components.push(variable(parser, &source[idx..idx + len], span)?);
+                    // the variable was already read
+                    chars.nth(source[idx + 1..idx + len].chars().count() - 1);
+                },
+            },
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() || components.is_empty() {
+        components.push(StrComponent::Literal(literal));
+    }
+    Ok(components)
+}
+
 // this parser is more of a helper than anything, to avoid repeating the matching code
 // for unknown variables and so.
-impl<'source> Parse<'source> for (StrComponent, Range<usize>) {
+impl<'source> Parse<'source> for (Vec<StrComponent>, Range<usize>) {
     fn parse(parser: &mut Parser<'source>) -> ParseRes<Self> {
-        let res = match parser.current_token()?.unwrap() {
+        let components = match parser.current_token()?.unwrap() {
             Token::Variable => {
                 let span = parser.current_token_span().clone();
-                (
-                    match parser.parse() {
-                        Ok(lookup) => StrComponent::Lookup(lookup),
-                        // unknown variables become literals with the at they came with
-                        Err(e) if matches!(e.kind, ParseErrorKind::UnknownVariable) => {
-                            StrComponent::Literal(parser.current_token_source().to_string())
-                        },
-                        // other errors are not caught though
-                        Err(e) => return Err(e),
-                    },
-                    span,
-                )
-            },
-            Token::Identifier => (
-                StrComponent::Literal(parser.current_token_source().to_string()),
-                parser.current_token_span().clone(),
-            ),
-            Token::RawIdentifier => {
-                // get token source character and remove colons
-                let mut tok_chars = parser.current_token_source().chars();
-                tok_chars.next();
-                tok_chars.next_back();
-
-                (
-                    StrComponent::Literal(tok_chars.collect()),
-                    parser.current_token_span().clone(),
-                )
+                let source = parser.current_token_source();
+                vec![variable(parser, source, span).map_err(|kind| parser.error(kind))?]
             },
+            Token::Identifier => vec![StrComponent::Literal(
+                parser.current_token_source().to_string(),
+            )],
+            Token::EscapedAt => vec![StrComponent::Literal("@".to_string())],
+            Token::QuotedString => quoted(parser).map_err(|kind| parser.error(kind))?,
             _ => unreachable!("The main str parser should have stopped on these already."),
         };
+        let span = parser.current_token_span().clone();
         parser.accept_current();
-        Ok(res)
+        Ok((components, span))
     }
 }
 
@@ -136,24 +245,36 @@ impl<'source> Parse<'source> for Str<'source> {
             let (mut str, mut last_span) =
                 {
                     parser.expect_one_of_tokens(
-                &[Token::Variable, Token::Identifier, Token::RawIdentifier],
+                &[Token::Variable, Token::Identifier, Token::QuotedString, Token::EscapedAt],
                 Some("interpolated strings need at least one variable or string without spaces"),
             )?;
-                    let (component, span) = parser.parse()?;
-                    (Self::new(component, span.clone()), span)
+                    let (components, span): (Vec<_>, _) = parser.parse()?;
+                    let mut components = components.into_iter();
+                    let mut str = Self::new(components.next().unwrap(), span.clone());
+                    // the rest of a quoted string goes right after its first component
+                    let span = str.extend_all(
+                        components,
+                        span.clone(),
+                        span.end..span.end,
+                        parser.source(),
+                    );
+                    (str, span)
                 };
             while parser
                 .current_token()?
                 .filter(|tok| {
                     matches!(
                         tok,
-                        Token::Variable | Token::Identifier | Token::RawIdentifier
+                        Token::Variable
+                            | Token::Identifier
+                            | Token::QuotedString
+                            | Token::EscapedAt
                     )
                 })
                 .is_some()
             {
-                let (next_component, next_span) = parser.parse()?;
-                last_span = str.extend(next_component, last_span, next_span, parser.source());
+                let (components, span) = parser.parse()?;
+                last_span = str.extend_all(components, last_span, span, parser.source());
             }
             Ok(str)
         })
@@ -167,15 +288,54 @@ impl interpreter::Resolve for Str<'_> {
         &self,
         cache: &mut interpreter::Cache,
     ) -> Result<interpreter::ExprResult, interpreter::ErrorKind> {
-        let mut str = String::new();
-        for x in &self.sequence {
-            match x {
-                StrComponent::Literal(lit) => str.push_str(lit),
-                StrComponent::Lookup(lookup) => {
-                    str.push_str(&cache.resolve(lookup)?.cast_to_string())
-                },
-            }
+        let bindings = cache.bindings();
+        resolve_sequence(&self.sequence, &bindings, cache)
+    }
+}
+
+impl Str<'_> {
+    /// The value of the string as a condition on its own: a lone lookup, even through
+    /// bindings, keeps its type, so `@duplicate` is a boolean instead of the text `false`
+    pub fn resolve_condition(
+        &self,
+        cache: &mut interpreter::Cache,
+    ) -> Result<interpreter::ExprResult, interpreter::ErrorKind> {
+        let bindings = cache.bindings();
+        match super::expand_bindings(&self.sequence, &bindings).as_slice() {
+            [StrComponent::Lookup(lookup)] => cache.resolve(lookup),
+            _ => resolve_sequence(&self.sequence, &bindings, cache),
         }
-        Ok(str.into())
     }
 }
+
+/// Resolve the components of an interpolated string, taking the values of the variables
+/// from `bindings`
+fn resolve_sequence(
+    sequence: &[StrComponent],
+    bindings: &[Binding],
+    cache: &mut interpreter::Cache,
+) -> Result<interpreter::ExprResult, interpreter::ErrorKind> {
+    let mut str = String::new();
+    for component in sequence {
+        let value = match component {
+            StrComponent::Literal(lit) => {
+                str.push_str(lit);
+                continue;
+            },
+            StrComponent::Lookup(lookup) => cache.resolve(lookup)?,
+            StrComponent::Variable(name) => {
+                match bindings.iter().rposition(|binding| &binding.name == name) {
+                    // a binding can only use the ones before it, so they never loop
+                    Some(pos) => resolve_sequence(&bindings[pos].value, &bindings[..pos], cache)?,
+                    None => {
+                        str.push('@');
+                        str.push_str(name);
+                        continue;
+                    },
+                }
+            },
+        };
+        str.push_str(&value.cast_to_string());
+    }
+    Ok(str.into())
+}
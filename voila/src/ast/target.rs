@@ -33,7 +33,13 @@ use super::Token;
 impl<'source> Parse<'source> for Target<'source> {
     fn parse(parser: &mut Parser<'source>) -> ParseRes<Self> {
         let res = match parser.expect_one_of_tokens(
-            &[Token::OpenBrace, Token::Identifier, Token::Variable],
+            &[
+                Token::OpenBrace,
+                Token::Identifier,
+                Token::Variable,
+                Token::QuotedString,
+                Token::EscapedAt,
+            ],
             Some("as the start of a target"),
         )? {
             Token::OpenBrace => {
@@ -49,7 +55,7 @@ impl<'source> Parse<'source> for Target<'source> {
                     span: start..end,
                 })
             },
-            Token::Identifier | Token::Variable => {
+            Token::Identifier | Token::Variable | Token::QuotedString | Token::EscapedAt => {
                 let start = parser.current_token_span().start;
                 let expr = parser.parse()?;
                 parser.expect_token(
@@ -83,20 +89,23 @@ use std::sync::{mpsc, Arc, Mutex};
 pub fn run_target(
     target: &Target,
     cache: Arc<Mutex<interpreter::Cache>>,
+    ctx: &interpreter::Context,
     pool: &rayon::ThreadPool,
     tx: mpsc::Sender<interpreter::ErrorKind>,
 ) -> Result<(), interpreter::ErrorKind> {
     let ok = target
         .condition
         .as_ref()
-        .map_or(Ok(true.into()), |expr| cache.lock().unwrap().resolve(expr))?
+        .map_or(Ok(true.into()), |expr| {
+            expr.resolve_condition(&mut cache.lock().unwrap())
+        })?
         .cast_to_bool()?;
     if !ok {
         return Ok(());
     }
 
     for cycle in &target.cycles {
-        super::run_cycle(cycle, cache.clone(), pool, tx.clone());
+        super::run_cycle(cycle, cache.clone(), ctx, pool, tx.clone());
     }
     Ok(())
 }
@@ -1,4 +1,5 @@
 use crate::ast::Script;
+use crate::Options;
 
 pub type VoilaByteCode = Vec<u8>;
 
@@ -13,3 +14,15 @@ impl<'source> From<VoilaByteCode> for Script<'source> {
         bincode::deserialize(&s[..]).unwrap()
     }
 }
+
+impl From<Options> for VoilaByteCode {
+    fn from(options: Options) -> Self {
+        bincode::serialize(&options).unwrap()
+    }
+}
+
+impl From<VoilaByteCode> for Options {
+    fn from(s: VoilaByteCode) -> Self {
+        bincode::deserialize(&s[..]).unwrap()
+    }
+}
@@ -1,8 +1,17 @@
 use std::path::PathBuf;
+use structopt::clap::{self, AppSettings};
 use structopt::StructOpt;
 
 pub fn get_cli_args() -> Cli {
-    Cli::from_args()
+    let cli = Cli::from_args();
+    if cli.command.is_none() && (cli.dir.is_none() || cli.source.is_none()) {
+        clap::Error::with_description(
+            "FOLDER and SOURCE are required unless a subcommand is used",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit()
+    }
+    cli
 }
 
 #[derive(StructOpt)]
@@ -14,9 +23,12 @@ Voila is a CLI tool for operating with files and directories in massive amounts
 Licensed under the MIT License
 Download at https://github.com/alonely0/voila/releases
 Source code at https://github.com/alonely0/voila"#,
-    version_short = "v"
+    version_short = "v",
+    setting = AppSettings::ArgsNegateSubcommands
 )]
 pub struct Cli {
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
     #[structopt(
         long,
         help = "Compile Voila script into a static binary embedding all runtime"
@@ -28,14 +40,96 @@ pub struct Cli {
         help = "Operate recursively inside the directory provided"
     )]
     pub recursive: bool,
+    #[structopt(
+        short,
+        long,
+        help = "Ask before deleting, moving, overwriting or running shell commands"
+    )]
+    pub interactive: bool,
+    #[structopt(
+        long,
+        name = "POLICY",
+        possible_values = &crate::Overwrite::OPTS,
+        help = "What to do when copy, move, create, gzc or gzd would overwrite something, unless the call says otherwise"
+    )]
+    pub overwrite: Option<crate::Overwrite>,
+    #[structopt(
+        long,
+        help = "Show what would be changed in the file system without changing it"
+    )]
+    pub dry_run: bool,
+    #[structopt(
+        long,
+        name = "JOURNAL",
+        help = "Record every change made to the file system, so it can be reverted with `voila undo JOURNAL`"
+    )]
+    pub journal: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "Compute every sum again instead of reusing the ones kept from previous runs"
+    )]
+    pub no_hash_cache: bool,
+    #[structopt(
+        long,
+        name = "SIZE",
+        default_value = "64MiB",
+        parse(try_from_str = parse_size),
+        help = "The biggest file whose whole content can be read with @content"
+    )]
+    pub max_content: u64,
+    #[structopt(
+        long,
+        name = "NAME=VALUE",
+        number_of_values = 1,
+        parse(try_from_str = parse_define),
+        help = "Bind @NAME to VALUE in the script, instead of the value of its `let @NAME = ...;` if it has one"
+    )]
+    pub define: Vec<(String, String)>,
+    #[structopt(
+        long,
+        help = "Take unknown variables as text, warning about them, instead of failing"
+    )]
+    pub lenient: bool,
     #[structopt(
         name = "FOLDER",
         help = "/something/path/to/folder or ./path/to/folder"
     )]
-    pub dir: PathBuf,
+    pub dir: Option<PathBuf>,
     #[structopt(
         name = "SOURCE",
         help = "for syntax & examples see the documentation, you can find it in the repository."
     )]
-    pub source: String,
+    pub source: Option<String>,
+}
+
+fn parse_size(source: &str) -> Result<u64, String> {
+    byte_unit::Byte::from_str(source)
+        .map(|size| size.get_bytes() as u64)
+        .map_err(|e| e.to_string())
+}
+
+fn parse_define(source: &str) -> Result<(String, String), String> {
+    let (name, value) = source
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, but got `{source}`"))?;
+    let name = name.strip_prefix('@').unwrap_or(name);
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!(
+            "`{name}` isn't a valid name, it can only have letters and digits"
+        ));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+#[derive(StructOpt)]
+pub enum Command {
+    #[structopt(about = "Revert the changes recorded in a journal, newest first")]
+    Undo {
+        #[structopt(name = "JOURNAL", help = "journal written by a run with `--journal`")]
+        journal: PathBuf,
+    },
+    #[structopt(
+        about = "Remove the sums of files that no longer exist or have changed from the sum cache"
+    )]
+    PruneCache,
 }
@@ -11,10 +11,18 @@ const TEMPDIR_ERR_MSG: &str = "Can't write to temporary directory";
 const ENV_READ_ERR_MSG: &str = "Can't access current directory";
 const ENV_WRITE_ERR_MSG: &str = "Can't change environment";
 
+/// The optional features of this build, which the compiled script is built with
+/// too, so it has the same lookups
+const FEATURES: [(&str, bool); 2] = [
+    ("git", cfg!(feature = "git")),
+    ("media", cfg!(feature = "media")),
+];
+
 /// Embeds a Voila Script into a binary through
 /// the `compiled_voila` crate. The source is
-/// statically linked into the binary.
-pub fn compile(vars: [&str; 3]) -> Result<(), &str> {
+/// statically linked into the binary, along with
+/// the options it was compiled with.
+pub fn compile(vars: [&str; 4]) -> Result<(), &str> {
     let source = include_bytes!("../../code.tar.gz").as_ref();
     let pwd = env::current_dir().map_err(|_| ENV_READ_ERR_MSG)?;
     let target_dir = &get_target_dir();
@@ -30,16 +38,25 @@ pub fn compile(vars: [&str; 3]) -> Result<(), &str> {
     // set dir so cargo knows where to run
     env::set_current_dir(target_dir).map_err(|_| ENV_WRITE_ERR_MSG)?;
 
+    let features = FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(feature, _)| *feature)
+        .collect::<Vec<_>>()
+        .join(",");
+
     // launch compiler & get exit code
     let compiler_exit_status = process::Command::new("cargo")
         .env("v_code", vars[0])
         .env("v_path", vars[1])
         .env("v_recursive", vars[2])
+        .env("v_options", vars[3])
         .arg("+nightly")
         .arg("build")
         .args(["-Z", "unstable-options"])
         .arg("--release")
-        .args(["--bin", "compiled_voila"])
+        .args(["-p", "compiled_voila", "--bin", "compiled_voila"])
+        .arg(format!("--features={features}"))
         .arg(format!("--out-dir={p}", p = pwd.display()))
         .status()
         .map_err(|_| COMPILER_ERR_MSG)?
@@ -1,4 +1,4 @@
-use ansi_term::{Colour::*, Style};
+use ansi_term::{Colour, Colour::*, Style};
 use std::error::Error;
 use std::fmt;
 use std::ops::Range;
@@ -99,6 +99,13 @@ where
 
 impl<T: fmt::Display, C: fmt::Display> fmt::Display for SourceError<T, C> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_as("error", Red, f)
+    }
+}
+
+impl<T: fmt::Display, C: fmt::Display> SourceError<T, C> {
+    /// Show it labelled as `label`, with its markers in `colour`
+    fn fmt_as(&self, label: &str, colour: Colour, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some((span, snippet)) = &self.snippet {
             write!(
                 f,
@@ -108,7 +115,7 @@ impl<T: fmt::Display, C: fmt::Display> fmt::Display for SourceError<T, C> {
   {separator}
 {line:3} {separator}   {snip}
   {separator}   {red}{markers}{end}"#,
-                error = Red.bold().paint("error"),
+                error = colour.bold().paint(label),
                 kind = Style::new().bold().paint(self.kind.to_string()),
                 arrow = Blue.bold().paint("-->"),
                 location = Yellow.bold().paint(format!(
@@ -119,12 +126,12 @@ impl<T: fmt::Display, C: fmt::Display> fmt::Display for SourceError<T, C> {
                 line = Blue.bold().paint((snippet.start.line + 1).to_string()),
                 separator = Blue.bold().paint("|"),
                 snip = &snippet.line,
-                red = Red.bold().prefix(),
-                end = Red.bold().suffix(),
+                red = colour.bold().prefix(),
+                end = colour.bold().suffix(),
                 markers = " ".repeat(snippet.start.col) + &"^".repeat(span.len())
             )
         } else {
-            write!(f, "error:  {kind}", kind = self.kind)
+            write!(f, "{label}:  {kind}", kind = self.kind)
         }?;
         for ctx in &self.contexts {
             write!(
@@ -136,3 +143,13 @@ impl<T: fmt::Display, C: fmt::Display> fmt::Display for SourceError<T, C> {
         Ok(())
     }
 }
+
+/// A [SourceError] that doesn't stop anything, shown as a warning
+#[derive(Debug)]
+pub struct SourceWarning<T, C>(pub SourceError<T, C>);
+
+impl<T: fmt::Display, C: fmt::Display> fmt::Display for SourceWarning<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_as("warning", Yellow, f)
+    }
+}
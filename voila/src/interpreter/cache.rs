@@ -2,9 +2,19 @@ use super::ErrorKind;
 use super::ExprResult;
 use super::Lookup;
 use super::LookupError;
+use super::{
+    any_line_matches, read_exif, read_sample, with_blocks, ByteCounts, Context, Duplicates,
+    ExifData, Hash, HashCache, Hasher, TextStats,
+};
+#[cfg(feature = "media")]
+use super::{identify, read_media, MediaInfo};
+#[cfg(feature = "git")]
+use super::{GitRepo, GitRepos};
+use crate::ast::{Binding, Pattern, SumKind};
 use std::collections::HashMap;
-use std::io::BufReader;
-use std::lazy::OnceCell;
+use std::io::{BufReader, Seek};
+use std::cell::OnceCell;
+use std::sync::Arc;
 
 /// A struct to store already computed variables (like sums, name, etc), file metadata,
 /// and a buffered reader for the file.
@@ -16,45 +26,240 @@ pub struct Cache {
     file: OnceCell<BufReader<std::fs::File>>,
     /// The file metadata, if it was requested anytime.
     metadata: OnceCell<std::fs::Metadata>,
+    /// The start of the file, if it was requested anytime.
+    sample: OnceCell<Vec<u8>>,
+    /// The EXIF metadata of the file, if it was requested anytime.
+    exif: OnceCell<Option<ExifData>>,
+    /// The metadata of the file as a media file, if it was requested anytime.
+    #[cfg(feature = "media")]
+    media: OnceCell<MediaInfo>,
     /// The file path, constant for all the cache lifetime.
     path: std::path::PathBuf,
+    /// Every sum used by the script, computed all at once when one of them is needed.
+    sums: Arc<[SumKind]>,
+    /// The regular expressions of the lookups of the script
+    patterns: Arc<HashMap<Pattern, regex::Regex>>,
+    /// The bindings of the script
+    bindings: Arc<[Binding]>,
+    /// Sums computed in previous runs, if they are kept
+    hash_cache: Option<Arc<HashCache>>,
+    /// Files of the run with the same contents, if the script needs them
+    duplicates: Option<Arc<Duplicates>>,
+    /// Repositories that the files of the run are in
+    #[cfg(feature = "git")]
+    git: Arc<GitRepos>,
+    /// The biggest file whose whole content can be read
+    max_content: Option<u64>,
+    /// The start of the run
+    now: chrono::NaiveDateTime,
 }
 
 impl Cache {
-    pub fn new(path: std::path::PathBuf) -> Self {
+    pub fn new(path: std::path::PathBuf, ctx: &Context) -> Self {
         Self {
             variables: HashMap::new(),
             metadata: OnceCell::new(),
             file: OnceCell::new(),
+            sample: OnceCell::new(),
+            exif: OnceCell::new(),
+            #[cfg(feature = "media")]
+            media: OnceCell::new(),
             path,
+            sums: ctx.sums.clone(),
+            patterns: ctx.patterns.clone(),
+            bindings: ctx.bindings.clone(),
+            hash_cache: ctx.hash_cache.clone(),
+            duplicates: ctx.duplicates.clone(),
+            #[cfg(feature = "git")]
+            git: ctx.git.clone(),
+            max_content: ctx.max_content,
+            now: ctx.now,
         }
     }
     pub fn get_path(&self) -> &std::path::PathBuf {
         &self.path
     }
+    pub fn bindings(&self) -> Arc<[Binding]> {
+        self.bindings.clone()
+    }
+    /// The compiled regular expression of `pattern`, which the script has
+    pub fn regex(&self, pattern: &Pattern) -> regex::Regex {
+        self.patterns[pattern].clone()
+    }
+    pub fn max_content(&self) -> Option<u64> {
+        self.max_content
+    }
+    /// The instant `now` refers to, in UTC
+    pub fn now(&self) -> chrono::NaiveDateTime {
+        self.now
+    }
+    pub fn duplicates(&self) -> Option<&Duplicates> {
+        self.duplicates.as_deref()
+    }
+    /// The git repository that the file is in, if any
+    #[cfg(feature = "git")]
+    pub fn git_repo(&self) -> Option<Arc<GitRepo>> {
+        self.git.repo_of(&self.path)
+    }
     pub fn get_file_mut(&mut self) -> Result<&mut BufReader<std::fs::File>, ErrorKind> {
         self.file.get_or_try_init(|| {
             std::fs::File::open(&self.path)
                 .map_err(ErrorKind::from)
                 .map(|file| BufReader::with_capacity(8192, file))
         })?;
-        Ok(self.file.get_mut().unwrap())
+        // every lookup reads the file from the start, no matter how much the previous ones read
+        let reader = self.file.get_mut().unwrap();
+        reader.rewind()?;
+        Ok(reader)
     }
     pub fn get_file_metadata(&mut self) -> Result<&std::fs::Metadata, ErrorKind> {
         self.metadata
             .get_or_try_init(|| std::fs::metadata(&self.path).map_err(ErrorKind::from))
     }
+    /// The start of the file, enough to identify what it contains
+    pub fn get_sample(&mut self) -> Result<&[u8], ErrorKind> {
+        if self.sample.get().is_none() {
+            let sample = read_sample(self.get_file_mut()?)?;
+            self.sample.set(sample).unwrap();
+        }
+        Ok(self.sample.get().unwrap())
+    }
+    /// The EXIF metadata of the file, if it's an image that has it
+    pub fn get_exif(&mut self) -> Result<Option<&ExifData>, ErrorKind> {
+        if self.exif.get().is_none() {
+            let exif = read_exif(self.get_file_mut()?);
+            self.exif.set(exif).unwrap();
+        }
+        Ok(self.exif.get().unwrap().as_ref())
+    }
+    /// The metadata of the file as an audio, video or document file
+    #[cfg(feature = "media")]
+    pub fn get_media(&mut self) -> Result<&MediaInfo, ErrorKind> {
+        if self.media.get().is_none() {
+            let kind = identify(self.get_sample()?);
+            let max_len = self.max_content;
+            let media = read_media(self.get_file_mut()?, kind, max_len)?;
+            self.media.set(media).unwrap();
+        }
+        Ok(self.media.get().unwrap())
+    }
     pub fn get_lookup(&self, lookup: Lookup) -> Result<&ExprResult, ErrorKind> {
         self.variables
             .get(&lookup)
             .ok_or_else(|| ErrorKind::from(LookupError::new(lookup)))
     }
-    pub fn resolve_var(&mut self, lookup: Lookup) -> Result<ExprResult, ErrorKind> {
-        if self.variables.get(&lookup).is_none() {
+    pub fn resolve_var(&mut self, lookup: &Lookup) -> Result<ExprResult, ErrorKind> {
+        if !self.variables.contains_key(lookup) {
             let res = lookup.cached_resolve(self)?;
-            self.variables.insert(lookup, res);
+            self.variables.insert(lookup.clone(), res);
+        }
+        Ok(self.variables[lookup].clone())
+    }
+    /// Compute all the sums of the script in a single read of the file, caching them.
+    /// `requested` is computed even if the script doesn't use it. Sums that are in
+    /// the hash cache aren't computed again.
+    pub fn compute_sums(&mut self, requested: SumKind) -> Result<ExprResult, ErrorKind> {
+        let mut kinds = self.sums.to_vec();
+        if !kinds.contains(&requested) {
+            kinds.push(requested);
+        }
+        let hash_cache = self.hash_cache.clone();
+        let metadata = match hash_cache {
+            Some(_) => Some(self.get_file_metadata()?.clone()),
+            None => None,
+        };
+        if let (Some(hash_cache), Some(metadata)) = (&hash_cache, &metadata) {
+            kinds.retain(|&kind| match hash_cache.get(metadata, kind) {
+                Some(sum) => {
+                    self.variables.insert(Lookup::Sum(kind), sum.into());
+                    false
+                },
+                None => true,
+            });
+        }
+        if kinds.is_empty() {
+            return Ok(self.variables[&Lookup::Sum(requested)].clone());
+        }
+
+        let mut hashers: Vec<_> = kinds
+            .iter()
+            .map(|&kind| Hasher::select_from_sum(kind))
+            .collect();
+        with_blocks(self.get_file_mut()?, |block| {
+            hashers
+                .iter_mut()
+                .for_each(|hasher| hasher.update_block(block))
+        })?;
+        let sums: Vec<_> = kinds
+            .into_iter()
+            .zip(hashers)
+            .map(|(kind, hasher)| (kind, hasher.end_hash()))
+            .collect();
+        if let (Some(hash_cache), Some(metadata)) = (&hash_cache, &metadata) {
+            hash_cache.insert(&self.path, metadata, &sums);
+        }
+        for (kind, sum) in sums {
+            self.variables.insert(Lookup::Sum(kind), sum.into());
+        }
+        Ok(self.variables[&Lookup::Sum(requested)].clone())
+    }
+    /// Whether the file is bigger than `--max-content`, so `@content` can't be read whole
+    pub fn content_too_large(&mut self) -> bool {
+        match self.max_content {
+            // if there's no metadata, reading the content fails anyway
+            Some(max) => self.get_file_metadata().is_ok_and(|m| m.len() > max),
+            None => false,
+        }
+    }
+    /// Whether any line of the file matches `regex`, without reading all of it at once
+    pub fn content_matches(&mut self, regex: &regex::Regex) -> Result<bool, ErrorKind> {
+        Ok(any_line_matches(self.get_file_mut()?, regex)?)
+    }
+    /// Compute all the text statistics in a single read of the file, caching them.
+    pub fn compute_text_stats(&mut self, requested: &Lookup) -> Result<ExprResult, ErrorKind> {
+        let mut stats = TextStats::new();
+        with_blocks(self.get_file_mut()?, |block| stats.update_block(block))?;
+        let stats = stats.finish();
+        for (lookup, value) in [
+            (Lookup::LineCount, ExprResult::from(stats.lines as f64)),
+            (Lookup::WordCount, ExprResult::from(stats.words as f64)),
+            (Lookup::CharCount, ExprResult::from(stats.chars as f64)),
+            (
+                Lookup::LongestLine,
+                ExprResult::from(stats.longest_line as f64),
+            ),
+            (Lookup::Encoding, ExprResult::from(stats.encoding())),
+        ] {
+            self.variables.insert(lookup, value);
+        }
+        Ok(self.variables[requested].clone())
+    }
+    /// Count the bytes of the file in a single read, caching its entropy and NUL bytes.
+    /// An entropy over the first KiBs of the file reads only those.
+    pub fn compute_byte_counts(&mut self, requested: &Lookup) -> Result<ExprResult, ErrorKind> {
+        use std::io::Read;
+
+        let limit = match requested {
+            Lookup::Entropy(Some(kib)) => *kib as u64 * 1024,
+            _ => u64::MAX,
+        };
+        let mut counts = ByteCounts::default();
+        with_blocks(&mut self.get_file_mut()?.take(limit), |block| {
+            counts.update_block(block)
+        })?;
+        if let Lookup::Entropy(Some(_)) = requested {
+            return Ok(counts.entropy().into());
+        }
+        for (lookup, value) in [
+            (Lookup::Entropy(None), ExprResult::from(counts.entropy())),
+            (
+                Lookup::NullBytes,
+                ExprResult::from(counts.nul_bytes() as f64),
+            ),
+        ] {
+            self.variables.insert(lookup, value);
         }
-        Ok(self.variables[&lookup].clone())
+        Ok(self.variables[requested].clone())
     }
     /// Alternative to [`Resolve::resolve`]
     pub fn resolve<C: Resolve>(&mut self, resolved: &C) -> Result<ExprResult, ErrorKind> {
@@ -0,0 +1,125 @@
+use super::{Hash, HashCache, Hasher};
+use crate::ast::SumKind;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Bytes read from the start of each file to rule out most of the files with the same size
+const PARTIAL_LEN: u64 = 4096;
+
+/// Files with the same contents
+#[derive(Debug)]
+struct Group {
+    /// The file that the rest are copies of: the one modified first, or the first
+    /// by path if there's a tie
+    original: PathBuf,
+    len: usize,
+}
+
+/// Groups of files with the same contents, found before running the script so every
+/// file can know about the rest. Files are compared by size first, then by the sum of
+/// their first bytes, and only then by the sum of their whole contents.
+#[derive(Debug, Default)]
+pub struct Duplicates {
+    groups: HashMap<PathBuf, Arc<Group>>,
+}
+
+impl Duplicates {
+    /// Look for duplicates among `files`. Files that can't be read are taken as unique,
+    /// and so are empty files.
+    pub fn find<I>(files: I, hash_cache: Option<&HashCache>) -> Self
+    where
+        I: Iterator<Item = PathBuf>,
+    {
+        let mut by_size: HashMap<u64, Vec<(PathBuf, fs::Metadata)>> = HashMap::new();
+        for file in files {
+            match fs::metadata(&file) {
+                Ok(metadata) if metadata.is_file() && metadata.len() > 0 => by_size
+                    .entry(metadata.len())
+                    .or_default()
+                    .push((file, metadata)),
+                _ => (),
+            }
+        }
+        let candidates = by_size.into_values().filter(|files| files.len() > 1);
+
+        let by_start = regroup(candidates, |(file, _)| {
+            sum_of(&mut fs::File::open(file)?.take(PARTIAL_LEN), SumKind::Xxh3)
+        });
+        let by_contents = regroup(by_start, |(file, metadata)| {
+            if let Some(sum) = hash_cache.and_then(|cache| cache.get(metadata, SumKind::Blake3)) {
+                return Ok(sum);
+            }
+            let sum = sum_of(&mut fs::File::open(file)?, SumKind::Blake3)?;
+            if let Some(cache) = hash_cache {
+                cache.insert(file, metadata, &[(SumKind::Blake3, sum.clone())]);
+            }
+            Ok(sum)
+        });
+
+        let mut groups = HashMap::new();
+        for files in by_contents {
+            let original = files
+                .iter()
+                .min_by_key(|(file, metadata)| {
+                    (metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), file)
+                })
+                .map(|(file, _)| file.clone())
+                .unwrap();
+            let group = Arc::new(Group {
+                original,
+                len: files.len(),
+            });
+            groups.extend(files.into_iter().map(|(file, _)| (file, group.clone())));
+        }
+        Self { groups }
+    }
+
+    /// Whether the file is a copy of another one
+    pub fn is_duplicate(&self, file: &Path) -> bool {
+        self.groups
+            .get(file)
+            .is_some_and(|group| group.original != file)
+    }
+
+    /// How many other files have the same contents
+    pub fn count(&self, file: &Path) -> usize {
+        self.groups.get(file).map_or(0, |group| group.len - 1)
+    }
+
+    /// The file that `file` is a copy of, or itself if it isn't a copy
+    pub fn original<'a>(&'a self, file: &'a Path) -> &'a Path {
+        self.groups.get(file).map_or(file, |group| &group.original)
+    }
+}
+
+/// Split each group by the key of its files, keeping only those that are still shared by
+/// more than one file. Files whose key can't be computed are dropped.
+fn regroup<G, K>(groups: G, key: K) -> Vec<Vec<(PathBuf, fs::Metadata)>>
+where
+    G: IntoIterator<Item = Vec<(PathBuf, fs::Metadata)>>,
+    K: Fn(&(PathBuf, fs::Metadata)) -> io::Result<String> + Sync,
+{
+    groups
+        .into_iter()
+        .flat_map(|files| {
+            let keyed: Vec<_> = files
+                .into_par_iter()
+                .filter_map(|file| key(&file).ok().map(|key| (key, file)))
+                .collect();
+            let mut by_key: HashMap<String, Vec<_>> = HashMap::new();
+            for (key, file) in keyed {
+                by_key.entry(key).or_default().push(file);
+            }
+            by_key.into_values().filter(|files| files.len() > 1)
+        })
+        .collect()
+}
+
+fn sum_of<R: Read>(reader: &mut R, kind: SumKind) -> io::Result<String> {
+    Hasher::select_from_sum(kind).hash_reader(reader)
+}
@@ -0,0 +1,48 @@
+/// How many times each byte value appears in the contents of a file
+#[derive(Debug)]
+pub struct ByteCounts {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl Default for ByteCounts {
+    fn default() -> Self {
+        Self {
+            counts: [0; 256],
+            total: 0,
+        }
+    }
+}
+
+impl ByteCounts {
+    /// Count the next block of the file
+    pub fn update_block(&mut self, block: &[u8]) {
+        block
+            .iter()
+            .for_each(|&byte| self.counts[byte as usize] += 1);
+        self.total += block.len() as u64;
+    }
+
+    /// Shannon entropy of the bytes, in bits per byte: from 0 (a single repeated
+    /// byte, or nothing) to 8 (random data, like encrypted or compressed files)
+    pub fn entropy(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let total = self.total as f64;
+        -self
+            .counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total;
+                p * p.log2()
+            })
+            .sum::<f64>()
+    }
+
+    /// How many bytes are NUL
+    pub fn nul_bytes(&self) -> u64 {
+        self.counts[0]
+    }
+}
@@ -35,6 +35,8 @@ pub enum ErrorKind {
     LookupError,
     CastError,
     ArgCountMismatched,
+    Aborted,
+    ContentTooLarge,
 }
 
 impl Error for ErrorKind {
@@ -44,6 +46,8 @@ impl Error for ErrorKind {
             Self::LookupError(err) => err,
             Self::CastError(err) => err,
             Self::ArgCountMismatched(err) => err,
+            Self::Aborted(err) => err,
+            Self::ContentTooLarge(err) => err,
         })
     }
 }
@@ -103,3 +107,41 @@ impl fmt::Display for ArgCountMismatched {
         write!(f, "{function} expected at least {expected_count} arguments, but received {actual_count} instead", function = self.function, expected_count = self.function.minimum_arg_count(), actual_count = self.got)
     }
 }
+
+/// The user asked to stop the run from an interactive prompt
+#[derive(Debug)]
+pub struct Aborted;
+
+impl Error for Aborted {}
+
+impl fmt::Display for Aborted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("run aborted by the user")
+    }
+}
+
+/// `@content` was used on a file bigger than the maximum set for it
+#[derive(Debug)]
+pub struct ContentTooLarge {
+    path: std::path::PathBuf,
+    max: u64,
+}
+
+impl ContentTooLarge {
+    pub const fn new(path: std::path::PathBuf, max: u64) -> Self {
+        Self { path, max }
+    }
+}
+
+impl Error for ContentTooLarge {}
+
+impl fmt::Display for ContentTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the content of {path} is bigger than the maximum of {max}, raise it with `--max-content` or use `@content ~= <pattern>`, `@grep` or `@lines`, which don't read it whole",
+            path = self.path.display(),
+            max = byte_unit::Byte::from_bytes(self.max as u128).get_appropriate_unit(true)
+        )
+    }
+}
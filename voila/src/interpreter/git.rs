@@ -0,0 +1,173 @@
+use git2::{Oid, Repository, Status};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// The repositories that the files of the run are in, opened the first time that one
+/// of their files asks for them
+#[derive(Default)]
+pub struct GitRepos {
+    /// The repository of each directory, if it is inside one
+    dirs: Mutex<HashMap<PathBuf, Option<Arc<GitRepo>>>>,
+    /// The repositories found, by their `.git` directory
+    repos: Mutex<HashMap<PathBuf, Arc<GitRepo>>>,
+}
+
+impl std::fmt::Debug for GitRepos {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.repos.lock().unwrap().keys())
+            .finish()
+    }
+}
+
+impl GitRepos {
+    /// The repository that `path` is in, found by looking for a `.git` directory in
+    /// the directories that contain it
+    pub fn repo_of(&self, path: &Path) -> Option<Arc<GitRepo>> {
+        let dir = path.parent().unwrap_or(path).to_path_buf();
+        if let Some(repo) = self.dirs.lock().unwrap().get(&dir) {
+            return repo.clone();
+        }
+        let repo = Repository::discover(&dir)
+            .ok()
+            .filter(|repo| !repo.is_bare())
+            .map(|repo| {
+                self.repos
+                    .lock()
+                    .unwrap()
+                    .entry(repo.path().to_path_buf())
+                    .or_insert_with(|| Arc::new(GitRepo::new(repo)))
+                    .clone()
+            });
+        self.dirs.lock().unwrap().insert(dir, repo.clone());
+        repo
+    }
+}
+
+/// The last commit that changed a file
+#[derive(Debug, Clone)]
+pub struct LastCommit {
+    pub author: String,
+    pub time: SystemTime,
+}
+
+/// A repository with a working directory
+pub struct GitRepo {
+    repo: Mutex<Repository>,
+    workdir: PathBuf,
+    /// The history from `HEAD`, walked only as far as the files asked for need. The
+    /// walk holds the repository, so files of other directories of it wait for it
+    history: Mutex<Option<History>>,
+}
+
+impl GitRepo {
+    fn new(repo: Repository) -> Self {
+        let workdir = repo.workdir().unwrap_or_else(|| repo.path()).to_path_buf();
+        let workdir = workdir.canonicalize().unwrap_or(workdir);
+        Self {
+            repo: Mutex::new(repo),
+            workdir,
+            history: Mutex::new(None),
+        }
+    }
+
+    /// The path of the file from the root of the repository
+    fn relative(&self, path: &Path) -> Option<PathBuf> {
+        let path = path.canonicalize().ok()?;
+        path.strip_prefix(&self.workdir).ok().map(Path::to_path_buf)
+    }
+
+    /// Whether the file is `tracked` (and unchanged), `modified`, `untracked` or `ignored`,
+    /// empty if it has no status
+    pub fn status(&self, path: &Path) -> &'static str {
+        let relative = match self.relative(path) {
+            Some(relative) => relative,
+            None => return "",
+        };
+        let repo = self.repo.lock().unwrap();
+        match repo.status_file(&relative) {
+            Ok(status) if status.is_ignored() => "ignored",
+            Ok(status) if status.is_wt_new() => "untracked",
+            Ok(status) if status == Status::CURRENT => "tracked",
+            Ok(_) => "modified",
+            // files that are neither in the index nor in the working tree have none
+            Err(_) => "",
+        }
+    }
+
+    /// The last commit that changed the file, if it was ever committed. Recently changed
+    /// files are found quickly, but the first file that was never committed walks the
+    /// whole history.
+    pub fn last_commit(&self, path: &Path) -> Option<LastCommit> {
+        let relative = self.relative(path)?;
+        let mut history = self.history.lock().unwrap();
+        let repo = self.repo.lock().unwrap();
+        let history = history.get_or_insert_with(|| History::new(&repo).unwrap_or_default());
+        while !history.files.contains_key(&relative) {
+            match history.next_commit(&repo) {
+                Ok(true) => (),
+                Ok(false) => break,
+                // a commit that can't be read ends the walk, with what was found before it
+                Err(_) => {
+                    history.pending.clear();
+                    break;
+                },
+            }
+        }
+        history.files.get(&relative).cloned()
+    }
+}
+
+/// The commits from `HEAD` that weren't looked at yet, and the last commit of the files
+/// changed in the ones that were
+#[derive(Default)]
+struct History {
+    /// Oldest first, so the next one to look at is the last
+    pending: Vec<Oid>,
+    files: HashMap<PathBuf, LastCommit>,
+}
+
+impl History {
+    /// Go through the commits from `HEAD`, newest first. Merges are compared to their
+    /// first parent, like `git log` does.
+    fn new(repo: &Repository) -> Result<Self, git2::Error> {
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME | git2::Sort::REVERSE)?;
+        walk.push_head()?;
+        Ok(Self {
+            pending: walk.collect::<Result<_, _>>()?,
+            files: HashMap::new(),
+        })
+    }
+
+    /// Look at the next commit, keeping it for the files that it changes and no newer
+    /// one did. It's false when every commit was looked at.
+    fn next_commit(&mut self, repo: &Repository) -> Result<bool, git2::Error> {
+        let id = match self.pending.pop() {
+            Some(id) => id,
+            None => return Ok(false),
+        };
+        let commit = repo.find_commit(id)?;
+        let parent = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), None)?;
+        for path in diff.deltas().filter_map(|delta| delta.new_file().path()) {
+            if !self.files.contains_key(path) {
+                let author = commit.author();
+                let time = Duration::from_secs(author.when().seconds().max(0) as u64);
+                self.files.insert(
+                    path.to_path_buf(),
+                    LastCommit {
+                        author: author.name().unwrap_or_default().to_string(),
+                        time: SystemTime::UNIX_EPOCH + time,
+                    },
+                );
+            }
+        }
+        Ok(true)
+    }
+}
@@ -11,6 +11,7 @@ pub trait Hash: Sized {
     }
 }
 
+use blake3::Hasher as Blake3;
 use md5::Context as Md5;
 use ring::digest::Context as RingContext;
 use ring::digest::{
@@ -18,12 +19,24 @@ use ring::digest::{
 };
 use sha2::Digest;
 use sha2::Sha224;
+use sha3::Sha3_256;
+use xxhash_rust::xxh3::Xxh3;
 
+/// CRC-32C (Castagnoli), accumulated block by block
+#[derive(Default)]
+pub struct Crc32c(u32);
+
+// hashers live for a single sum, so the size of the biggest one (BLAKE3) isn't worth a box
+#[allow(clippy::large_enum_variant)]
 #[enum_dispatch(Hash)]
 pub enum Hasher {
     RingContext,
     Md5,
     Sha224,
+    Sha3_256,
+    Blake3,
+    Xxh3,
+    Crc32c,
 }
 
 impl Hash for Sha224 {
@@ -35,6 +48,42 @@ impl Hash for Sha224 {
     }
 }
 
+impl Hash for Sha3_256 {
+    fn update_block(&mut self, block: &[u8]) {
+        self.update(block);
+    }
+    fn end_hash(self) -> String {
+        format!("{:x}", self.finalize())
+    }
+}
+
+impl Hash for Blake3 {
+    fn update_block(&mut self, block: &[u8]) {
+        self.update(block);
+    }
+    fn end_hash(self) -> String {
+        self.finalize().to_hex().to_string()
+    }
+}
+
+impl Hash for Xxh3 {
+    fn update_block(&mut self, block: &[u8]) {
+        self.update(block);
+    }
+    fn end_hash(self) -> String {
+        format!("{:016x}", self.digest())
+    }
+}
+
+impl Hash for Crc32c {
+    fn update_block(&mut self, block: &[u8]) {
+        self.0 = crc32c::crc32c_append(self.0, block);
+    }
+    fn end_hash(self) -> String {
+        format!("{:08x}", self.0)
+    }
+}
+
 impl Hash for Md5 {
     fn update_block(&mut self, block: &[u8]) {
         self.consume(block);
@@ -68,6 +117,10 @@ impl Hasher {
             SumKind::Sha256 => Self::from(RingContext::new(&Sha256)),
             SumKind::Sha384 => Self::from(RingContext::new(&Sha384)),
             SumKind::Sha512 => Self::from(RingContext::new(&Sha512)),
+            SumKind::Sha3 => Self::from(Sha3_256::new()),
+            SumKind::Blake3 => Self::from(Blake3::new()),
+            SumKind::Xxh3 => Self::from(Xxh3::new()),
+            SumKind::Crc32c => Self::from(Crc32c::default()),
         }
     }
 }
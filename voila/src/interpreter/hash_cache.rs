@@ -0,0 +1,147 @@
+use crate::ast::SumKind;
+use path_absolutize::*;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Identifies a version of a file: if any of these change, its sums are computed again
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    device: u64,
+    inode: u64,
+    size: u64,
+    /// Nanoseconds since the unix epoch
+    modified: i128,
+}
+
+impl Key {
+    #[cfg(unix)]
+    fn of(metadata: &fs::Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+
+        Some(Self {
+            device: metadata.dev(),
+            inode: metadata.ino(),
+            size: metadata.len(),
+            modified: metadata.mtime() as i128 * 1_000_000_000 + metadata.mtime_nsec() as i128,
+        })
+    }
+
+    // without inodes there's no reliable way to tell files apart, so nothing is cached
+    #[cfg(not(unix))]
+    fn of(_: &fs::Metadata) -> Option<Self> {
+        None
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry {
+    /// Where the file was last seen, to find out whether the entry is stale
+    path: PathBuf,
+    sums: HashMap<SumKind, String>,
+}
+
+/// Sums of files computed in previous runs, so they aren't computed again
+/// as long as the files don't change.
+#[derive(Debug)]
+pub struct HashCache {
+    location: PathBuf,
+    entries: Mutex<HashMap<Key, Entry>>,
+    /// Whether there is something new to save
+    dirty: AtomicBool,
+}
+
+impl HashCache {
+    /// `$XDG_CACHE_HOME/voila/sums`, or `~/.cache/voila/sums` if it isn't set
+    pub fn default_location() -> Option<PathBuf> {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .map(|dir| dir.join("voila").join("sums"))
+    }
+
+    /// Load the cache stored at `location`. A missing or unreadable cache is
+    /// taken as an empty one, which only means that sums will be computed again.
+    pub fn load(location: PathBuf) -> Self {
+        let entries = fs::read(&location)
+            .ok()
+            .and_then(|bytes| bincode::deserialize(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            location,
+            entries: Mutex::new(entries),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// The sum of the file with this metadata, if it was computed before
+    pub fn get(&self, metadata: &fs::Metadata, kind: SumKind) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(&Key::of(metadata)?)?.sums.get(&kind).cloned()
+    }
+
+    pub fn insert(&self, path: &Path, metadata: &fs::Metadata, sums: &[(SumKind, String)]) {
+        let (key, path) = match (Key::of(metadata), path.absolutize()) {
+            (Some(key), Ok(path)) => (key, path.into_owned()),
+            _ => return,
+        };
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(key).or_insert_with(|| Entry {
+            path: path.clone(),
+            sums: HashMap::new(),
+        });
+        entry.path = path;
+        entry.sums.extend(sums.iter().cloned());
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Remove the entries of files that no longer exist or have changed since their
+    /// sums were computed, returning how many were removed
+    pub fn prune(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|key, entry| {
+            fs::metadata(&entry.path)
+                .ok()
+                .and_then(|metadata| Key::of(&metadata))
+                == Some(*key)
+        });
+        let removed = before - entries.len();
+        if removed > 0 {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        removed
+    }
+
+    /// Write the cache back to disk if anything changed
+    pub fn save(&self) -> io::Result<()> {
+        if !self.dirty.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        if let Some(dir) = self.location.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let bytes = bincode::serialize(&*self.entries.lock().unwrap()).map_err(io::Error::other)?;
+        // write it aside and move it into place, so other runs never read half of it
+        let mut temp = self.location.clone().into_os_string();
+        temp.push(format!(".{}", std::process::id()));
+        fs::write(&temp, bytes)?;
+        fs::rename(&temp, &self.location)
+    }
+}
+
+/// Remove the stale entries of the sum cache, returning how many were removed
+pub fn prune_hash_cache() -> Result<usize, Box<dyn Error>> {
+    let location = HashCache::default_location()
+        .ok_or("couldn't find the cache directory, neither XDG_CACHE_HOME nor HOME are set")?;
+    let cache = HashCache::load(location);
+    let removed = cache.prune();
+    cache.save()?;
+    Ok(removed)
+}
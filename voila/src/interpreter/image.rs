@@ -0,0 +1,76 @@
+use super::ExprResult;
+use chrono::{NaiveDate, NaiveDateTime};
+use exif::{Exif, In, Tag, Value};
+use std::io::{BufRead, Seek};
+
+/// Width and height of the image, read from its header
+pub fn image_size<R: BufRead + Seek>(reader: &mut R) -> Option<(usize, usize)> {
+    let size = imagesize::reader_size(reader).ok()?;
+    Some((size.width, size.height))
+}
+
+/// The EXIF fields of an image
+pub struct ExifData(Exif);
+
+impl std::fmt::Debug for ExifData {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.0.fields().map(|field| field.tag))
+            .finish()
+    }
+}
+
+/// The EXIF fields of the image, if it has any
+pub fn read_exif<R: BufRead + Seek>(reader: &mut R) -> Option<ExifData> {
+    exif::Reader::new()
+        .read_from_container(reader)
+        .ok()
+        .map(ExifData)
+}
+
+/// The value of an EXIF field of the main image: dates become datetimes, and the
+/// rest are shown like cameras do (`f/2.8`, `1/250 s`...). It's empty if there's no such field.
+pub fn exif_value(ExifData(exif): &ExifData, tag: Tag) -> ExprResult {
+    if let Some(datetime) = exif_datetime(exif, tag) {
+        return ExprResult::DateTime(datetime);
+    }
+    exif.get_field(tag, In::PRIMARY)
+        .map(|field| {
+            field
+                .display_value()
+                .with_unit(exif)
+                .to_string()
+                .trim_matches('"')
+                .to_string()
+        })
+        .unwrap_or_default()
+        .into()
+}
+
+/// When the picture was taken, or else when it was digitized or last changed
+pub fn date_taken(ExifData(exif): &ExifData) -> Option<NaiveDateTime> {
+    [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+        .into_iter()
+        .find_map(|tag| exif_datetime(exif, tag))
+}
+
+fn exif_datetime(exif: &Exif, tag: Tag) -> Option<NaiveDateTime> {
+    if ![Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime].contains(&tag) {
+        return None;
+    }
+    let ascii = match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(ascii) => ascii.first()?,
+        _ => return None,
+    };
+    let datetime = exif::DateTime::from_ascii(ascii).ok()?;
+    NaiveDate::from_ymd_opt(
+        datetime.year.into(),
+        datetime.month.into(),
+        datetime.day.into(),
+    )?
+    .and_hms_opt(
+        datetime.hour.into(),
+        datetime.minute.into(),
+        datetime.second.into(),
+    )
+}
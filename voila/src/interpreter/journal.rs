@@ -0,0 +1,395 @@
+use crate::ast::{copy_destination, permissions_mode, set_permissions_mode, Function};
+use path_absolutize::*;
+use serde_derive::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// A single step done by a call, with everything needed to revert it.
+/// All the paths are absolute, so the journal can be replayed from anywhere.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum Operation {
+    /// A file or directory was written at `path`. If something was already
+    /// there, it was saved to `backup` before being overwritten.
+    Created {
+        path: PathBuf,
+        backup: Option<PathBuf>,
+    },
+    /// `from` was renamed to `to`
+    Renamed { from: PathBuf, to: PathBuf },
+    /// The permissions of `path` were changed, `mode` holds the old ones
+    ModeChanged { path: PathBuf, mode: u32 },
+    /// The access and modification times of `path` were changed from these
+    TimesChanged {
+        path: PathBuf,
+        accessed: SystemTime,
+        modified: SystemTime,
+    },
+    /// A directory that didn't exist before was created
+    DirCreated(PathBuf),
+    /// `path` was removed, and its contents were saved to `backup` beforehand
+    Deleted { path: PathBuf, backup: PathBuf },
+    /// The call can't be reverted (e.g. `shell`). It is recorded anyway
+    /// so the user knows about it when undoing.
+    Irreversible(Vec<String>),
+    // operations for some platforms only go last, so the others keep their place in
+    // journals
+    /// The owner of `path` was changed from `uid` and `gid`
+    #[cfg(unix)]
+    OwnerChanged { path: PathBuf, uid: u32, gid: u32 },
+}
+
+/// What a single call did to the file system
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Entry {
+    function: Function,
+    operations: Vec<Operation>,
+}
+
+/// An append-only record of the changes made to the file system during a run.
+/// Every destructive call backs up what it is going to overwrite or remove
+/// before executing, so `voila undo` can restore it afterwards.
+#[derive(Debug)]
+pub struct Journal {
+    file: Mutex<fs::File>,
+    /// Directory where the backups are stored
+    backups: PathBuf,
+    /// Prefix for the backups of this run, so runs appending to the same
+    /// journal don't step on each other
+    run_id: u128,
+    /// Number of backups taken in this run
+    taken: AtomicUsize,
+}
+
+impl Journal {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            backups: backups_dir(path)?,
+            run_id: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0),
+            taken: AtomicUsize::new(0),
+        })
+    }
+
+    /// Save whatever is at `path`, if there is anything, so it can be restored later
+    fn backup(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        if fs::symlink_metadata(path).is_err() {
+            return Ok(None);
+        }
+        fs::create_dir_all(&self.backups)?;
+        let backup = self.backups.join(format!(
+            "{run}-{n}",
+            run = self.run_id,
+            n = self.taken.fetch_add(1, Ordering::Relaxed)
+        ));
+        copy_recursively(path, &backup)?;
+        Ok(Some(backup))
+    }
+
+    /// Figure out what a call is going to do and back up everything it may destroy.
+    /// This must be called with the resolved arguments **before** executing the call.
+    /// Returns `None` for calls that don't touch the file system.
+    pub fn prepare(&self, function: Function, args: &[String]) -> io::Result<Option<Entry>> {
+        let mut operations = Vec::new();
+        match function {
+            // renames and manifests are recorded by themselves when they're done
+            Function::Print { .. } | Function::Rename { .. } | Function::Manifest { .. } => {
+                return Ok(None)
+            },
+            Function::Shell { .. } | Function::Child { .. } => {
+                operations.push(Operation::Irreversible(args.to_vec()))
+            },
+            Function::Mkdir { .. } => {
+                for dir in args {
+                    operations.extend(missing_dirs(&absolute(dir)?).map(Operation::DirCreated));
+                }
+            },
+            Function::Delete { .. } => {
+                for target in args {
+                    let path = absolute(target)?;
+                    if let Some(backup) = self.backup(&path)? {
+                        operations.push(Operation::Deleted { path, backup });
+                    }
+                }
+            },
+            Function::Create { .. } | Function::GzipCompress { .. } => {
+                let dest = match function {
+                    Function::Create { .. } => &args[0],
+                    _ => &args[1],
+                };
+                let path = absolute(dest)?;
+                let backup = self.backup(&path)?;
+                operations.push(Operation::Created { path, backup });
+            },
+            Function::Copy { .. } | Function::Move { .. } => {
+                let source = absolute(&args[0])?;
+                let path = copy_destination(&source, absolute(&args[1])?);
+                let backup = self.backup(&path)?;
+                operations.push(Operation::Created { path, backup });
+                if matches!(function, Function::Move { .. }) {
+                    if let Some(backup) = self.backup(&source)? {
+                        operations.push(Operation::Deleted {
+                            path: source,
+                            backup,
+                        });
+                    }
+                }
+            },
+            Function::Chmod { .. } => {
+                let path = absolute(&args[0])?;
+                let mode = permissions_mode(&fs::metadata(&path)?);
+                operations.push(Operation::ModeChanged { path, mode });
+            },
+            #[cfg(unix)]
+            Function::Chown { .. } => {
+                use std::os::unix::fs::MetadataExt;
+
+                let path = absolute(&args[0])?;
+                let metadata = fs::metadata(&path)?;
+                operations.push(Operation::OwnerChanged {
+                    path,
+                    uid: metadata.uid(),
+                    gid: metadata.gid(),
+                });
+            },
+            Function::Touch { .. } => {
+                let path = absolute(&args[0])?;
+                operations.push(match fs::metadata(&path) {
+                    Ok(metadata) => Operation::TimesChanged {
+                        path,
+                        accessed: metadata.accessed()?,
+                        modified: metadata.modified()?,
+                    },
+                    Err(_) => Operation::Created { path, backup: None },
+                });
+            },
+            Function::GzipDecompress { .. } => {
+                use flate2::read::GzDecoder;
+                use tar::Archive;
+
+                let dest = absolute(&args[1])?;
+                operations.extend(missing_dirs(&dest).map(Operation::DirCreated));
+                let mut archive = Archive::new(GzDecoder::new(fs::File::open(&args[0])?));
+                for archived in archive.entries()? {
+                    let archived = archived?;
+                    let path = dest.join(archived.path()?);
+                    if archived.header().entry_type().is_dir() {
+                        operations.extend(missing_dirs(&path).map(Operation::DirCreated));
+                    } else {
+                        let backup = self.backup(&path)?;
+                        operations.push(Operation::Created { path, backup });
+                    }
+                }
+            },
+        }
+        Ok(Some(Entry {
+            function,
+            operations,
+        }))
+    }
+
+    /// Append the entry of a call that was executed successfully
+    pub fn record(&self, entry: Entry) -> io::Result<()> {
+        let bytes = bincode::serialize(&entry).map_err(io::Error::other)?;
+        // write the whole entry at once, so entries of calls running
+        // at the same time don't get mixed up
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&bytes)?;
+        file.flush()
+    }
+
+    /// Append a rename that has just been done
+    pub fn record_rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.record(Entry {
+            function: Function::Rename { safe: true },
+            operations: vec![Operation::Renamed {
+                from: absolute(from)?,
+                to: absolute(to)?,
+            }],
+        })
+    }
+
+    /// Back up whatever is at `path`, write it with `write` and record it
+    pub fn record_write<F>(&self, function: Function, path: &Path, write: F) -> io::Result<()>
+    where
+        F: FnOnce() -> io::Result<()>,
+    {
+        let path = absolute(path)?;
+        let backup = self.backup(&path)?;
+        let entry = Entry {
+            function,
+            operations: vec![Operation::Created { path, backup }],
+        };
+        match write() {
+            Ok(()) => self.record(entry),
+            Err(e) => {
+                self.discard(entry);
+                Err(e)
+            },
+        }
+    }
+
+    /// Throw away the backups of an entry whose call failed
+    pub fn discard(&self, entry: Entry) {
+        for op in entry.operations {
+            if let Operation::Created {
+                backup: Some(backup),
+                ..
+            }
+            | Operation::Deleted { backup, .. } = op
+            {
+                remove(&backup).unwrap_or(());
+            }
+        }
+    }
+}
+
+/// Revert every call recorded in the journal, newest first. Operations that fail
+/// to be reverted are reported and skipped; if all of them succeed, the journal and
+/// its backups are removed.
+pub fn undo(path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    let mut entries = Vec::new();
+    loop {
+        match bincode::deserialize_from::<_, Entry>(&mut reader) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(ref io) if io.kind() == io::ErrorKind::UnexpectedEof => {
+                    break
+                },
+                _ => return Err(e),
+            },
+        }
+    }
+
+    let mut failed = 0;
+    for entry in entries.into_iter().rev() {
+        for op in entry.operations.into_iter().rev() {
+            if let Operation::Irreversible(ref args) = op {
+                eprintln!(
+                    "`{function}` can't be undone, skipping `{args}`",
+                    function = entry.function,
+                    args = args.join(" ")
+                );
+            } else if let Err(e) = revert(&op) {
+                failed += 1;
+                eprintln!("couldn't undo `{}` ({op:?}): {e}", entry.function);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(UndoError { failed }.into());
+    }
+    fs::remove_file(path)?;
+    remove(&backups_dir(path)?).map_err(Into::into)
+}
+
+fn revert(op: &Operation) -> io::Result<()> {
+    match op {
+        Operation::Created { path, backup } => {
+            remove(path)?;
+            if let Some(backup) = backup {
+                copy_recursively(backup, path)?;
+            }
+            Ok(())
+        },
+        Operation::DirCreated(path) => match fs::remove_dir(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+        Operation::Deleted { path, backup } => copy_recursively(backup, path),
+        Operation::Renamed { from, to } => fs::rename(to, from),
+        Operation::ModeChanged { path, mode } => set_permissions_mode(path, *mode),
+        #[cfg(unix)]
+        Operation::OwnerChanged { path, uid, gid } => {
+            std::os::unix::fs::chown(path, Some(*uid), Some(*gid))
+        },
+        Operation::TimesChanged {
+            path,
+            accessed,
+            modified,
+        } => filetime::set_file_times(
+            path,
+            filetime::FileTime::from_system_time(*accessed),
+            filetime::FileTime::from_system_time(*modified),
+        ),
+        // nothing to do, `undo` already warns about these
+        Operation::Irreversible(_) => Ok(()),
+    }
+}
+
+#[derive(Debug)]
+pub struct UndoError {
+    failed: usize,
+}
+
+impl Error for UndoError {}
+
+impl fmt::Display for UndoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{failed} operations couldn't be undone, the journal has been kept",
+            failed = self.failed
+        )
+    }
+}
+
+fn backups_dir(journal: &Path) -> io::Result<PathBuf> {
+    let mut name = journal.file_name().unwrap_or_default().to_owned();
+    name.push(".backups");
+    Ok(absolute(journal)?.with_file_name(name))
+}
+
+fn absolute<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    path.as_ref().absolutize().map(Into::into)
+}
+
+/// Directories that would be created by `fs::create_dir_all(path)`, outermost first
+fn missing_dirs(path: &Path) -> impl Iterator<Item = PathBuf> {
+    let mut missing = path
+        .ancestors()
+        .take_while(|dir| !dir.exists())
+        .map(Path::to_path_buf)
+        .collect::<Vec<_>>();
+    missing.reverse();
+    missing.into_iter()
+}
+
+fn copy_recursively(source: &Path, dest: &Path) -> io::Result<()> {
+    if !source.is_dir() {
+        return fs::copy(source, dest).map(|_| ());
+    }
+    for entry in walkdir::WalkDir::new(source) {
+        let entry = entry?;
+        let target = dest.join(entry.path().strip_prefix(source).unwrap());
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::ops::ControlFlow;
+
+/// Go through the lines of `reader`, numbered from 1, until `f` breaks. Lines are taken
+/// as UTF-8 (invalid bytes are replaced) and without their line break, and only one of
+/// them is kept in memory at a time.
+pub fn for_each_line<R, F>(reader: &mut R, mut f: F) -> io::Result<()>
+where
+    R: BufRead,
+    F: FnMut(usize, &str) -> ControlFlow<()>,
+{
+    let mut buf = Vec::new();
+    let mut number = 0;
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+        let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if f(number, &String::from_utf8_lossy(line)).is_break() {
+            return Ok(());
+        }
+    }
+}
+
+/// The lines from `start` to `end` (both included, and counting from 1) joined by
+/// newlines. Without an `end`, it goes up to the last line.
+pub fn line_range<R: BufRead>(
+    reader: &mut R,
+    start: usize,
+    end: Option<usize>,
+) -> io::Result<String> {
+    let mut lines = Vec::new();
+    for_each_line(reader, |number, line| {
+        if number >= start {
+            lines.push(line.to_string());
+        }
+        match end {
+            Some(end) if number >= end => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        }
+    })?;
+    Ok(lines.join("\n"))
+}
+
+/// The last `count` lines, joined by newlines
+pub fn tail<R: BufRead>(reader: &mut R, count: usize) -> io::Result<String> {
+    let mut lines = VecDeque::with_capacity(count);
+    if count > 0 {
+        for_each_line(reader, |_, line| {
+            if lines.len() == count {
+                lines.pop_front();
+            }
+            lines.push_back(line.to_string());
+            ControlFlow::Continue(())
+        })?;
+    }
+    Ok(Vec::from(lines).join("\n"))
+}
+
+/// The lines that match `regex`, joined by newlines
+pub fn grep<R: BufRead>(reader: &mut R, regex: &regex::Regex) -> io::Result<String> {
+    let mut lines = Vec::new();
+    for_each_line(reader, |_, line| {
+        if regex.is_match(line) {
+            lines.push(line.to_string());
+        }
+        ControlFlow::Continue(())
+    })?;
+    Ok(lines.join("\n"))
+}
+
+/// Whether any line matches `regex`, stopping at the first one that does
+pub fn any_line_matches<R: BufRead>(reader: &mut R, regex: &regex::Regex) -> io::Result<bool> {
+    let mut found = false;
+    for_each_line(reader, |_, line| {
+        found = regex.is_match(line);
+        if found {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })?;
+    Ok(found)
+}
+
+/// How many lines match `regex`
+pub fn grep_count<R: BufRead>(reader: &mut R, regex: &regex::Regex) -> io::Result<usize> {
+    let mut count = 0;
+    for_each_line(reader, |_, line| {
+        if regex.is_match(line) {
+            count += 1;
+        }
+        ControlFlow::Continue(())
+    })?;
+    Ok(count)
+}
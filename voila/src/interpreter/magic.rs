@@ -0,0 +1,104 @@
+use std::io::{self, Read};
+
+/// Bytes read from the start of a file to find out what it contains
+const SAMPLE_LEN: u64 = 8192;
+
+/// A kind of file, as told by its contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileKind {
+    /// Short name, like `png` or `elf`
+    pub name: &'static str,
+    pub mime: &'static str,
+}
+
+impl FileKind {
+    const fn new(name: &'static str, mime: &'static str) -> Self {
+        Self { name, mime }
+    }
+}
+
+pub const TEXT: FileKind = FileKind::new("text", "text/plain");
+pub const DATA: FileKind = FileKind::new("data", "application/octet-stream");
+pub const EMPTY: FileKind = FileKind::new("empty", "inode/x-empty");
+pub const ELF: FileKind = FileKind::new("elf", "application/x-executable");
+
+/// Bytes that a format has at the given offsets
+type Signature = &'static [(usize, &'static [u8])];
+
+/// Known file formats, the first one that matches is taken
+#[rustfmt::skip]
+const MAGIC: &[(Signature, FileKind)] = &[
+    (&[(0, b"\x89PNG\r\n\x1a\n")], FileKind::new("png", "image/png")),
+    (&[(0, b"\xff\xd8\xff")], FileKind::new("jpeg", "image/jpeg")),
+    (&[(0, b"GIF87a")], FileKind::new("gif", "image/gif")),
+    (&[(0, b"GIF89a")], FileKind::new("gif", "image/gif")),
+    (&[(0, b"RIFF"), (8, b"WEBP")], FileKind::new("webp", "image/webp")),
+    (&[(0, b"II*\0")], FileKind::new("tiff", "image/tiff")),
+    (&[(0, b"MM\0*")], FileKind::new("tiff", "image/tiff")),
+    (&[(0, b"\0\0\x01\0")], FileKind::new("ico", "image/x-icon")),
+    (&[(0, b"%PDF-")], FileKind::new("pdf", "application/pdf")),
+    (&[(0, b"PK\x03\x04")], FileKind::new("zip", "application/zip")),
+    (&[(0, b"PK\x05\x06")], FileKind::new("zip", "application/zip")),
+    (&[(0, b"\x1f\x8b")], FileKind::new("gzip", "application/gzip")),
+    (&[(0, b"BZh")], FileKind::new("bzip2", "application/x-bzip2")),
+    (&[(0, b"\xfd7zXZ\0")], FileKind::new("xz", "application/x-xz")),
+    (&[(0, b"\x28\xb5\x2f\xfd")], FileKind::new("zstd", "application/zstd")),
+    (&[(0, b"7z\xbc\xaf\x27\x1c")], FileKind::new("7z", "application/x-7z-compressed")),
+    (&[(0, b"Rar!\x1a\x07")], FileKind::new("rar", "application/vnd.rar")),
+    (&[(257, b"ustar")], FileKind::new("tar", "application/x-tar")),
+    (&[(0, b"\x7fELF")], ELF),
+    (&[(0, b"\xfe\xed\xfa\xce")], FileKind::new("macho", "application/x-mach-binary")),
+    (&[(0, b"\xfe\xed\xfa\xcf")], FileKind::new("macho", "application/x-mach-binary")),
+    (&[(0, b"\xce\xfa\xed\xfe")], FileKind::new("macho", "application/x-mach-binary")),
+    (&[(0, b"\xcf\xfa\xed\xfe")], FileKind::new("macho", "application/x-mach-binary")),
+    (&[(0, b"MZ")], FileKind::new("pe", "application/vnd.microsoft.portable-executable")),
+    (&[(0, b"\0asm")], FileKind::new("wasm", "application/wasm")),
+    (&[(0, b"SQLite format 3\0")], FileKind::new("sqlite", "application/vnd.sqlite3")),
+    (&[(0, b"ID3")], FileKind::new("mp3", "audio/mpeg")),
+    (&[(0, b"\xff\xfb")], FileKind::new("mp3", "audio/mpeg")),
+    (&[(0, b"\xff\xf3")], FileKind::new("mp3", "audio/mpeg")),
+    (&[(0, b"\xff\xf2")], FileKind::new("mp3", "audio/mpeg")),
+    (&[(0, b"fLaC")], FileKind::new("flac", "audio/flac")),
+    (&[(0, b"OggS")], FileKind::new("ogg", "audio/ogg")),
+    (&[(0, b"RIFF"), (8, b"WAVE")], FileKind::new("wav", "audio/wav")),
+    (&[(0, b"RIFF"), (8, b"AVI ")], FileKind::new("avi", "video/x-msvideo")),
+    (&[(4, b"ftyp")], FileKind::new("mp4", "video/mp4")),
+    (&[(0, b"\x1a\x45\xdf\xa3")], FileKind::new("mkv", "video/x-matroska")),
+];
+
+/// Read the start of the file, enough to identify it
+pub fn read_sample<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut sample = Vec::new();
+    reader.take(SAMPLE_LEN).read_to_end(&mut sample)?;
+    Ok(sample)
+}
+
+/// What the file that starts with `sample` contains. Files of no known format are
+/// either text or data.
+pub fn identify(sample: &[u8]) -> FileKind {
+    if sample.is_empty() {
+        return EMPTY;
+    }
+    MAGIC
+        .iter()
+        .find(|(signature, _)| {
+            signature
+                .iter()
+                .all(|(offset, bytes)| sample[*offset.min(&sample.len())..].starts_with(bytes))
+        })
+        .map(|(_, kind)| *kind)
+        .unwrap_or(if is_text(sample) { TEXT } else { DATA })
+}
+
+/// Whether the file that starts with `sample` is text: it has no NUL bytes and
+/// is valid UTF-8 (which includes ASCII)
+pub fn is_text(sample: &[u8]) -> bool {
+    if sample.is_empty() || sample.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        // the sample may have cut the last character in half
+        Err(e) => e.error_len().is_none(),
+    }
+}
@@ -0,0 +1,139 @@
+use super::Journal;
+use crate::ast::{Function, SumKind};
+use path_absolutize::*;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Name of the manifest that lists sums of `kind` in a directory, like `SHA256SUMS`
+pub fn manifest_name(kind: SumKind) -> String {
+    format!("{}SUMS", kind.to_string().to_uppercase())
+}
+
+/// The sum of `path` that was recorded beforehand, either in a sidecar next to it
+/// (`file.iso.sha256`) or in the manifest of its directory (`SHA256SUMS`), in that order
+pub fn expected_sum(path: &Path, kind: SumKind) -> io::Result<Option<String>> {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(format!(".{kind}"));
+    if let Some(content) = read_if_exists(Path::new(&sidecar))? {
+        return Ok(content.split_whitespace().next().map(str::to_lowercase));
+    }
+
+    let path = path.absolutize()?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("/"));
+    let content = match read_if_exists(&dir.join(manifest_name(kind)))? {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+    for (sum, listed) in content.lines().filter_map(parse_line) {
+        if dir.join(listed).absolutize()? == path {
+            return Ok(Some(sum.to_lowercase()));
+        }
+    }
+    Ok(None)
+}
+
+fn read_if_exists(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Parse a line of a manifest, in either of the formats of coreutils:
+/// `<sum>  <file>` (`*<file>` in binary mode) or the BSD-style `SHA256 (<file>) = <sum>`
+fn parse_line(line: &str) -> Option<(&str, String)> {
+    if let Some((tag, sum)) = line.rsplit_once(") = ") {
+        let (_, file) = tag.split_once(" (")?;
+        return Some((sum.trim(), file.to_string()));
+    }
+    // file names with a backslash or a newline are escaped, and the line starts with `\`
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+    let (sum, file) = line.split_once(' ')?;
+    let file = file.strip_prefix([' ', '*']).unwrap_or(file);
+    Some((
+        sum,
+        if escaped {
+            file.replace("\\n", "\n").replace("\\\\", "\\")
+        } else {
+            file.to_string()
+        },
+    ))
+}
+
+/// A manifest that is being built
+#[derive(Debug)]
+struct Pending {
+    kind: SumKind,
+    sums: Vec<(PathBuf, String)>,
+}
+
+/// Manifests are written once every file has been processed, so each of
+/// them is written all at once with its files sorted.
+#[derive(Debug, Default)]
+pub struct Manifests {
+    pending: Mutex<HashMap<PathBuf, Pending>>,
+}
+
+impl Manifests {
+    /// Add the sum of `file` to the manifest at `manifest`
+    pub fn queue(&self, manifest: &str, kind: SumKind, file: &Path, sum: String) -> io::Result<()> {
+        let manifest = Path::new(manifest).absolutize()?.into_owned();
+        let file = file.absolutize()?.into_owned();
+        // the manifest may be in the directory that's being walked
+        if file == manifest {
+            return Ok(());
+        }
+        let mut pending = self.pending.lock().unwrap();
+        let pending = pending.entry(manifest.clone()).or_insert_with(|| Pending {
+            kind,
+            sums: Vec::new(),
+        });
+        if pending.kind != kind {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "the manifest {manifest} can't list both {listed} and {kind} sums",
+                    manifest = manifest.display(),
+                    listed = pending.kind
+                ),
+            ));
+        }
+        pending.sums.push((file, sum));
+        Ok(())
+    }
+
+    /// Write every manifest, replacing what was in their place
+    pub fn write(&self, journal: Option<&Journal>) -> io::Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        for (manifest, Pending { mut sums, .. }) in pending {
+            sums.sort();
+            let dir = manifest.parent().unwrap_or_else(|| Path::new("/"));
+            let mut content = String::new();
+            for (file, sum) in sums {
+                // paths are relative to the manifest, so `sha256sum -c` can be run next to it
+                let name = file.strip_prefix(dir).unwrap_or(&file).to_string_lossy();
+                if name.contains(['\\', '\n']) {
+                    let name = name.replace('\\', "\\\\").replace('\n', "\\n");
+                    content.push_str(&format!("\\{sum}  {name}\n"));
+                } else {
+                    content.push_str(&format!("{sum}  {name}\n"));
+                }
+            }
+            let write = || fs::write(&manifest, &content);
+            match journal {
+                Some(journal) => {
+                    journal.record_write(Function::Manifest { safe: true }, &manifest, write)?
+                },
+                None => write()?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+use super::FileKind;
+use crate::ast::MediaTag;
+use regex::bytes::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::LazyLock as SyncLazy;
+use std::time::Duration;
+
+/// How much of a PDF is read when `--max-content` doesn't say it
+const MAX_PDF_LEN: u64 = 64 * 1024 * 1024;
+
+static OBJSTM: SyncLazy<Regex> = SyncLazy::new(|| Regex::new(r"/Type\s*/ObjStm").unwrap());
+static PAGES: SyncLazy<Regex> = SyncLazy::new(|| Regex::new(r"/Type\s*/Pages\b").unwrap());
+static COUNT: SyncLazy<Regex> = SyncLazy::new(|| Regex::new(r"/Count\s+(\d+)").unwrap());
+static PAGE: SyncLazy<Regex> = SyncLazy::new(|| Regex::new(r"/Type\s*/Page\b").unwrap());
+static INFO_REF: SyncLazy<Regex> =
+    SyncLazy::new(|| Regex::new(r"/Info\s+(\d+)\s+(\d+)\s+R").unwrap());
+static TITLE: SyncLazy<Regex> = SyncLazy::new(|| Regex::new(r"/Title\s*([(<])").unwrap());
+static AUTHOR: SyncLazy<Regex> = SyncLazy::new(|| Regex::new(r"/Author\s*([(<])").unwrap());
+
+/// Metadata of an audio, video or document file
+#[derive(Debug, Default)]
+pub struct MediaInfo {
+    pub tags: HashMap<MediaTag, String>,
+    /// How long the recording lasts
+    pub duration: Option<Duration>,
+    /// Pages of the document
+    pub pages: Option<usize>,
+}
+
+/// Read the metadata of the file: ID3 tags of MP3s, Vorbis comments of FLACs, the
+/// `ilst` atoms of MP4s and the information dictionary of PDFs. It's empty if the kind
+/// of file isn't one of those or its metadata can't be parsed. PDFs are read whole, up
+/// to `max_len` or 64 MiB without it.
+pub fn read_media<R: Read + Seek>(
+    reader: &mut R,
+    kind: FileKind,
+    max_len: Option<u64>,
+) -> io::Result<MediaInfo> {
+    let mut info = MediaInfo::default();
+    match kind.name {
+        "mp3" => read_id3(reader, &mut info),
+        // a truncated file has the metadata that could be read before its end
+        "flac" => match read_flac(reader, &mut info) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+            res => res?,
+        },
+        "mp4" => {
+            let end = reader.seek(SeekFrom::End(0))?;
+            match read_mp4(reader, 0, end, &mut info) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+                res => res?,
+            }
+        },
+        "pdf" => {
+            let mut content = Vec::new();
+            reader
+                .take(max_len.unwrap_or(MAX_PDF_LEN))
+                .read_to_end(&mut content)?;
+            read_pdf(&content, &mut info);
+        },
+        _ => (),
+    }
+    Ok(info)
+}
+
+fn read_id3<R: Read + Seek>(reader: &mut R, info: &mut MediaInfo) {
+    use id3::TagLike;
+
+    // files without tags have no metadata, and that's not an error
+    let tag = match id3::v1v2::read_from(reader) {
+        Ok(tag) => tag,
+        Err(_) => return,
+    };
+    for (media_tag, value) in [
+        (MediaTag::Title, tag.title().map(str::to_string)),
+        (MediaTag::Artist, tag.artist().map(str::to_string)),
+        (MediaTag::Album, tag.album().map(str::to_string)),
+        (MediaTag::Year, tag.year().map(|year| year.to_string())),
+        (MediaTag::Genre, tag.genre_parsed().map(Cow::into_owned)),
+        (MediaTag::Track, tag.track().map(|track| track.to_string())),
+    ] {
+        if let Some(value) = value {
+            info.tags.insert(media_tag, value);
+        }
+    }
+    info.duration = tag.duration().map(|ms| Duration::from_millis(ms.into()));
+}
+
+fn read_flac<R: Read>(reader: &mut R, info: &mut MediaInfo) -> io::Result<()> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    loop {
+        let mut header = [0; 4];
+        reader.read_exact(&mut header)?;
+        let last = header[0] & 0x80 != 0;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut block = vec![0; len];
+        reader.read_exact(&mut block)?;
+        match header[0] & 0x7f {
+            // STREAMINFO: 20 bits of sample rate and, 8 bits later, 36 bits of samples
+            0 if len >= 18 => {
+                let rate = u32::from_be_bytes([0, block[10], block[11], block[12]]) >> 4;
+                let mut samples = [0; 8];
+                samples[3..].copy_from_slice(&block[13..18]);
+                let samples = u64::from_be_bytes(samples) & 0xf_ffff_ffff;
+                if rate > 0 {
+                    info.duration = Some(Duration::from_secs_f64(samples as f64 / rate as f64));
+                }
+            },
+            4 => read_vorbis_comments(&block, info),
+            _ => (),
+        }
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+/// Takes bytes from the start of a slice
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn u32_le(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+}
+
+/// Vorbis comments are `KEY=value` strings, after the name of the encoder
+fn read_vorbis_comments(block: &[u8], info: &mut MediaInfo) {
+    let mut bytes = Bytes(block);
+    let vendor_len = bytes.u32_le().unwrap_or(0);
+    bytes.take(vendor_len as usize);
+    let count = bytes.u32_le().unwrap_or(0);
+    for _ in 0..count {
+        let comment = match bytes.u32_le().and_then(|len| bytes.take(len as usize)) {
+            Some(comment) => String::from_utf8_lossy(comment),
+            None => return,
+        };
+        let (key, value) = match comment.split_once('=') {
+            Some(comment) => comment,
+            None => continue,
+        };
+        let media_tag = match key.to_uppercase().as_str() {
+            "TITLE" => MediaTag::Title,
+            "ARTIST" => MediaTag::Artist,
+            "ALBUM" => MediaTag::Album,
+            "DATE" | "YEAR" => MediaTag::Year,
+            "GENRE" => MediaTag::Genre,
+            "TRACKNUMBER" => MediaTag::Track,
+            _ => continue,
+        };
+        info.tags
+            .entry(media_tag)
+            .or_insert_with(|| value.to_string());
+    }
+}
+
+/// Go through the MP4 boxes between `start` and `end`, into the ones that lead to the
+/// duration (`moov/mvhd`) and the tags (`moov/udta/meta/ilst`)
+fn read_mp4<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    info: &mut MediaInfo,
+) -> io::Result<()> {
+    let mut pos = start;
+    while pos < end && end - pos >= 8 {
+        reader.seek(SeekFrom::Start(pos))?;
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let mut size = u32::from_be_bytes(header[..4].try_into().unwrap()) as u64;
+        let mut content = pos + 8;
+        match size {
+            // the size doesn't fit in 32 bits, so it follows
+            1 => {
+                let mut large = [0; 8];
+                reader.read_exact(&mut large)?;
+                size = u64::from_be_bytes(large);
+                content += 8;
+            },
+            // the box goes up to the end
+            0 => size = end - pos,
+            _ => (),
+        }
+        // a box smaller than its header would never move forward
+        if size < content - pos {
+            break;
+        }
+        let next = match pos.checked_add(size) {
+            Some(next) => next,
+            None => break,
+        };
+        let box_end = next.min(end);
+        match &header[4..] {
+            b"moov" | b"udta" | b"ilst" => read_mp4(reader, content, box_end, info)?,
+            b"meta" => {
+                // it starts with a version and flags, except when written by QuickTime
+                let mut peek = [0; 8];
+                reader.read_exact(&mut peek)?;
+                let skip = if &peek[4..] == b"hdlr" { 0 } else { 4 };
+                read_mp4(reader, content + skip, box_end, info)?
+            },
+            b"mvhd" => read_mvhd(reader, info)?,
+            kind => {
+                let media_tag = match kind {
+                    b"\xa9nam" => MediaTag::Title,
+                    b"\xa9ART" => MediaTag::Artist,
+                    b"\xa9alb" => MediaTag::Album,
+                    b"\xa9day" => MediaTag::Year,
+                    b"\xa9gen" => MediaTag::Genre,
+                    b"trkn" => MediaTag::Track,
+                    _ => {
+                        pos = next;
+                        continue;
+                    },
+                };
+                let mut item = vec![0; box_end.saturating_sub(content).min(64 * 1024) as usize];
+                reader.read_exact(&mut item)?;
+                if let Some(value) = ilst_value(&item, media_tag) {
+                    info.tags.insert(media_tag, value);
+                }
+            },
+        }
+        pos = next;
+    }
+    Ok(())
+}
+
+fn read_mvhd<R: Read>(reader: &mut R, info: &mut MediaInfo) -> io::Result<()> {
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    // the creation and modification times come first, in 32 or 64 bits
+    let (skip, duration_len) = if version[0] == 1 { (16, 8) } else { (8, 4) };
+    let mut fields = vec![0; skip + 4 + duration_len];
+    reader.read_exact(&mut fields)?;
+    let timescale = u32::from_be_bytes(fields[skip..skip + 4].try_into().unwrap());
+    let mut duration = [0; 8];
+    duration[8 - duration_len..].copy_from_slice(&fields[skip + 4..]);
+    if timescale > 0 {
+        info.duration = Some(Duration::from_secs_f64(
+            u64::from_be_bytes(duration) as f64 / timescale as f64,
+        ));
+    }
+    Ok(())
+}
+
+/// The value of the `data` box of an item of `ilst`
+fn ilst_value(item: &[u8], media_tag: MediaTag) -> Option<String> {
+    let len = u32::from_be_bytes(item.get(..4)?.try_into().ok()?) as usize;
+    if item.get(4..8)? != b"data" {
+        return None;
+    }
+    // after the type of the value and its locale
+    let value = item.get(16..len.min(item.len()))?;
+    if media_tag == MediaTag::Track {
+        // two reserved bytes, then the track and the total of tracks
+        return Some(u16::from_be_bytes(value.get(2..4)?.try_into().ok()?).to_string());
+    }
+    Some(String::from_utf8_lossy(value).into_owned())
+}
+
+fn read_pdf(content: &[u8], info: &mut MediaInfo) {
+    // since PDF 1.5 objects can be compressed together in streams, so those are searched too
+    let mut texts = vec![Cow::Borrowed(content)];
+    texts.extend(
+        streams(content)
+            .filter(|(dict, _)| OBJSTM.is_match(dict))
+            .filter_map(|(_, data)| inflate(data))
+            .map(Cow::Owned),
+    );
+
+    // the root of the page tree counts all the pages, and it's the biggest count
+    info.pages = texts
+        .iter()
+        .flat_map(|text| PAGES.find_iter(text).map(move |m| (text, m.start())))
+        .filter_map(|(text, pos)| enclosing_dict(text, pos))
+        .filter_map(|dict| {
+            let count = COUNT.captures(dict)?;
+            std::str::from_utf8(&count[1]).ok()?.parse().ok()
+        })
+        .max()
+        // without a page tree, the pages themselves are counted
+        .or_else(|| Some(texts.iter().map(|text| PAGE.find_iter(text).count()).sum()))
+        .filter(|&pages| pages > 0);
+
+    // the last update of the document has the current information dictionary
+    let dict = INFO_REF.captures_iter(content).last().and_then(|refs| {
+        let obj = [&refs[1], b" ", &refs[2], b" obj"].concat();
+        // the object number can't be the end of a bigger one
+        let start = content
+            .windows(obj.len())
+            .enumerate()
+            .rev()
+            .find(|&(i, window)| window == obj && (i == 0 || !content[i - 1].is_ascii_digit()))?
+            .0
+            + obj.len();
+        let open = start + find(&content[start..], b"<<")?;
+        enclosing_dict(content, open + 2)
+    });
+    if let Some(dict) = dict {
+        for (key, media_tag) in [(&TITLE, MediaTag::Title), (&AUTHOR, MediaTag::Author)] {
+            if let Some(value) = dict_string(dict, key).filter(|value| !value.is_empty()) {
+                info.tags.insert(media_tag, value);
+            }
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The dictionary (`<< ... >>`) that has the byte at `pos`
+fn enclosing_dict(text: &[u8], pos: usize) -> Option<&[u8]> {
+    let mut depth = 0;
+    let mut start = None;
+    for i in (1..pos.min(text.len())).rev() {
+        match &text[i - 1..=i] {
+            b">>" => depth += 1,
+            b"<<" if depth == 0 => {
+                start = Some(i - 1);
+                break;
+            },
+            b"<<" => depth -= 1,
+            _ => (),
+        }
+    }
+    let start = start?;
+    let mut depth = 0;
+    for i in start..text.len().saturating_sub(1) {
+        match &text[i..i + 2] {
+            b"<<" => depth += 1,
+            b">>" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..i + 2]);
+                }
+            },
+            _ => (),
+        }
+    }
+    None
+}
+
+/// The streams of the document, with the dictionary that describes each of them
+fn streams(content: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut rest = 0;
+    std::iter::from_fn(move || loop {
+        let keyword = rest + find(&content[rest..], b"stream")?;
+        let data = keyword + b"stream".len();
+        rest = data;
+        // `endstream` has `stream` in it too
+        let data = match &content[data..] {
+            [b'\r', b'\n', ..] => data + 2,
+            [b'\n', ..] => data + 1,
+            _ => continue,
+        };
+        let end = data + find(&content[data..], b"endstream")?;
+        rest = end;
+        let dict_end = content[..keyword].iter().rposition(|&b| b == b'>')?;
+        if let Some(dict) = enclosing_dict(content, dict_end) {
+            return Some((dict, &content[data..end]));
+        }
+    })
+}
+
+/// Decompress a stream of the document, up to a limit so it can't take all the memory
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut inflated = Vec::new();
+    flate2::read::ZlibDecoder::new(data)
+        .take(64 * 1024 * 1024)
+        .read_to_end(&mut inflated)
+        .ok()?;
+    Some(inflated)
+}
+
+/// The string value of `key` in the dictionary, either a literal (`(...)`) or in hex (`<...>`).
+/// The key is matched with its opening delimiter.
+fn dict_string(dict: &[u8], key: &Regex) -> Option<String> {
+    let captures = key.captures(dict)?;
+    let start = captures.get(1)?.end();
+    let bytes = if &captures[1] == b"(" {
+        literal_string(&dict[start..])
+    } else {
+        let end = start + dict[start..].iter().position(|&b| b == b'>')?;
+        let hex: Vec<u8> = dict[start..end]
+            .iter()
+            .copied()
+            .filter(u8::is_ascii_hexdigit)
+            .collect();
+        hex.chunks(2)
+            .map(|pair| {
+                let pair = if pair.len() == 2 {
+                    pair.to_vec()
+                } else {
+                    vec![pair[0], b'0']
+                };
+                u8::from_str_radix(std::str::from_utf8(&pair).ok()?, 16).ok()
+            })
+            .collect::<Option<_>>()?
+    };
+    Some(decode_text(&bytes))
+}
+
+/// The bytes of a literal string, from after its opening parenthesis
+fn literal_string(source: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut depth = 0;
+    let mut chars = source.iter().copied().peekable();
+    while let Some(b) = chars.next() {
+        match b {
+            b'(' => depth += 1,
+            b')' if depth == 0 => break,
+            b')' => depth -= 1,
+            b'\\' => {
+                let escaped = match chars.next() {
+                    Some(b'n') => b'\n',
+                    Some(b'r') => b'\r',
+                    Some(b't') => b'\t',
+                    Some(b'b') => 0x08,
+                    Some(b'f') => 0x0c,
+                    Some(b'\r') | Some(b'\n') => continue,
+                    Some(digit @ b'0'..=b'7') => {
+                        let mut value = (digit - b'0') as u32;
+                        for _ in 0..2 {
+                            match chars.peek() {
+                                Some(&digit @ b'0'..=b'7') => {
+                                    value = value * 8 + (digit - b'0') as u32;
+                                    chars.next();
+                                },
+                                _ => break,
+                            }
+                        }
+                        value as u8
+                    },
+                    Some(other) => other,
+                    None => break,
+                };
+                bytes.push(escaped);
+                continue;
+            },
+            _ => (),
+        }
+        bytes.push(b);
+    }
+    bytes
+}
+
+/// Text strings are either UTF-16 with a byte order mark or PDFDocEncoding,
+/// which is close enough to Latin-1
+fn decode_text(bytes: &[u8]) -> String {
+    match bytes {
+        [0xfe, 0xff, utf16 @ ..] => String::from_utf16_lossy(
+            &utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>(),
+        ),
+        _ => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
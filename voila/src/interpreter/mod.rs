@@ -1,21 +1,162 @@
 use super::ast::Lookup;
+use super::ast::Overwrite;
 use super::ast::Script;
 use rayon::ThreadPoolBuilder;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
 use std::sync::mpsc;
 use walkdir::WalkDir;
+mod duplicates;
+pub use duplicates::*;
+mod entropy;
+pub use entropy::*;
 mod error;
 pub use error::*;
+#[cfg(feature = "git")]
+mod git;
+#[cfg(feature = "git")]
+pub use git::*;
 mod cache;
 pub use cache::*;
 mod hash;
 pub use hash::*;
+mod hash_cache;
+pub use hash_cache::*;
+mod image;
+pub use image::*;
+mod journal;
+pub use journal::*;
+mod lines;
+pub use lines::*;
+mod magic;
+pub use magic::*;
+mod manifest;
+#[cfg(feature = "media")]
+mod media;
+pub use manifest::*;
+#[cfg(feature = "media")]
+pub use media::*;
+mod prompt;
+pub use prompt::*;
+mod rename;
+pub use rename::*;
+mod text_stats;
+pub use text_stats::*;
+mod time;
+pub use time::*;
+
+/// Settings for a whole run, not tied to any specific file. Compiled scripts
+/// keep the ones they were compiled with.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Options {
+    /// Where to record the changes made to the file system, if anywhere
+    pub journal: Option<std::path::PathBuf>,
+    /// Ask before executing destructive calls
+    pub interactive: bool,
+    /// What to do when a call writes over something that exists,
+    /// unless the call has its own policy
+    pub overwrite: Overwrite,
+    /// Show what would be done instead of doing it
+    pub dry_run: bool,
+    /// Keep the sums of the files between runs, in the user's cache directory
+    pub hash_cache: bool,
+    /// The biggest file whose whole content can be read with `@content`, in bytes
+    pub max_content: Option<u64>,
+    /// Values for bindings of the script, by their name
+    pub defines: Vec<(String, String)>,
+    /// Take unknown variables as text instead of failing
+    pub lenient: bool,
+}
+
+/// State shared by all the files during a run
+#[derive(Debug)]
+pub struct Context {
+    pub journal: Option<Journal>,
+    pub prompt: Option<Prompt>,
+    pub overwrite: Overwrite,
+    pub renames: Renames,
+    pub manifests: Manifests,
+    pub dry_run: bool,
+    pub max_content: Option<u64>,
+    /// The instant `now` refers to, in UTC. It's taken once when the run starts,
+    /// so every file is compared against the same one.
+    pub now: chrono::NaiveDateTime,
+    /// Every sum used by the script
+    pub sums: Arc<[crate::ast::SumKind]>,
+    /// The regular expressions of the lookups of the script, compiled once
+    pub patterns: Arc<HashMap<crate::ast::Pattern, regex::Regex>>,
+    /// The bindings of the script, resolved for each file that uses them
+    pub bindings: Arc<[crate::ast::Binding]>,
+    pub hash_cache: Option<Arc<HashCache>>,
+    pub duplicates: Option<Arc<Duplicates>>,
+    /// Repositories that the files are in, for the git lookups
+    #[cfg(feature = "git")]
+    pub git: Arc<GitRepos>,
+}
+
+impl Context {
+    pub fn new(
+        options: Options,
+        script: &Script,
+        directory: &std::path::Path,
+        recursive: bool,
+    ) -> std::io::Result<Self> {
+        let sums = script.sums();
+        let finds_duplicates = script.finds_duplicates();
+        // there's no point in loading the cache if no sum will be computed
+        let hash_cache = (options.hash_cache && (!sums.is_empty() || finds_duplicates))
+            .then(HashCache::default_location)
+            .flatten()
+            .map(|location| Arc::new(HashCache::load(location)));
+        // every file has to be seen before knowing whether any of them is a duplicate
+        let duplicates = finds_duplicates.then(|| {
+            Arc::new(Duplicates::find(
+                get_files(directory.to_owned(), recursive),
+                hash_cache.as_deref(),
+            ))
+        });
+        Ok(Self {
+            journal: options.journal.as_deref().map(Journal::open).transpose()?,
+            prompt: options.interactive.then(Prompt::default),
+            overwrite: options.overwrite,
+            renames: Renames::default(),
+            manifests: Manifests::default(),
+            dry_run: options.dry_run,
+            max_content: options.max_content,
+            now: chrono::Utc::now().naive_utc(),
+            sums: sums.into(),
+            // they were checked when they were parsed
+            patterns: Arc::new(
+                script
+                    .patterns()
+                    .into_iter()
+                    .map(|pattern| {
+                        let regex = regex::Regex::new(&pattern.0).unwrap();
+                        (pattern, regex)
+                    })
+                    .collect(),
+            ),
+            bindings: script.bindings.clone().into(),
+            hash_cache,
+            duplicates,
+            #[cfg(feature = "git")]
+            git: Arc::default(),
+        })
+    }
+    /// Whether the user stopped the run, so no more calls should be executed
+    pub fn aborted(&self) -> bool {
+        self.prompt.as_ref().is_some_and(Prompt::quit)
+    }
+}
 
 pub fn run(
     script: Script,
     directory: std::path::PathBuf,
     recursive: bool,
+    options: Options,
 ) -> Result<(), Box<dyn Error>> {
+    let ctx = &Context::new(options, &script, &directory, recursive)?;
     let pool = ThreadPoolBuilder::new()
         .num_threads(num_cpus::get())
         .build()
@@ -26,14 +167,10 @@ pub fn run(
     {
         let script = &script;
         pool.scope(move |s| {
-            for file in get_walker(directory, recursive)
-                .filter_map(Result::ok)
-                .map(|x| x.path().to_owned())
-                .filter(|x| x.is_file())
-            {
+            for file in get_files(directory, recursive) {
                 let tx = tx_.clone();
                 s.spawn(move |_| {
-                    super::ast::run_script(script, file, pool, tx);
+                    super::ast::run_script(script, file, ctx, pool, tx);
                 })
             }
         });
@@ -53,7 +190,20 @@ pub fn run(
     // }
 
     drop(tx);
-    rx.into_iter().next().map_or(Ok(()), |x| Err(x.into()))
+    // manifests list the files by the names they had while running, so they go first
+    let finished = if ctx.aborted() {
+        Ok(())
+    } else {
+        ctx.manifests
+            .write(ctx.journal.as_ref())
+            .and_then(|_| ctx.renames.apply(ctx.journal.as_ref(), ctx.dry_run))
+    };
+    let saved = ctx.hash_cache.as_deref().map_or(Ok(()), HashCache::save);
+    if let Some(e) = rx.into_iter().next() {
+        return Err(e.into());
+    }
+    finished?;
+    saved.map_err(Into::into)
 }
 
 // pub fn run_cycle(cycle: &Cycle, cache: Arc<Mutex<Cache>>) -> Result<(), ErrorKind> {
@@ -69,6 +219,17 @@ fn get_walker(directory: std::path::PathBuf, recursive: bool) -> walkdir::IntoIt
     .into_iter()
 }
 
+/// The files the script runs on
+fn get_files(
+    directory: std::path::PathBuf,
+    recursive: bool,
+) -> impl Iterator<Item = std::path::PathBuf> {
+    get_walker(directory, recursive)
+        .filter_map(Result::ok)
+        .map(|x| x.path().to_owned())
+        .filter(|x| x.is_file())
+}
+
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -85,6 +246,8 @@ pub enum ExprResult {
     Numeric(f64),
     Date(chrono::NaiveDate),
     Time(chrono::NaiveTime),
+    DateTime(chrono::NaiveDateTime),
+    Duration(chrono::Duration),
 }
 
 impl ExprResult {
@@ -95,13 +258,15 @@ impl ExprResult {
             Self::Numeric(f) => f.to_string(),
             Self::Date(d) => d.to_string(),
             Self::Time(t) => t.format("%H:%M:%S").to_string(),
+            Self::DateTime(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+            Self::Duration(d) => format_duration(d),
         }
     }
 
-    /// Try to reinterpret the value as another thing.
-    pub fn reinterpret(self) -> Self {
+    /// Try to reinterpret the value as another thing, with `now` being the start of the run.
+    pub fn reinterpret(self, now: chrono::NaiveDateTime) -> Self {
         match self {
-            Self::String(s) => Self::parse_str(&s),
+            Self::String(s) => Self::parse_str(&s, now),
             other => other,
         }
     }
@@ -123,6 +288,18 @@ impl ExprResult {
                     to: "boolean",
                 })
             },
+            Self::DateTime(_) => {
+                return Err(CastError::IncompatibleCast {
+                    from: "datetime",
+                    to: "boolean",
+                })
+            },
+            Self::Duration(_) => {
+                return Err(CastError::IncompatibleCast {
+                    from: "duration",
+                    to: "boolean",
+                })
+            },
         })
     }
 
@@ -141,6 +318,48 @@ impl ExprResult {
             Self::Time(t) => Ok(t
                 .signed_duration_since(chrono::NaiveTime::from_hms(0, 0, 0))
                 .num_seconds() as f64),
+            // seconds since the unix epoch
+            Self::DateTime(dt) => Ok(dt.and_utc().timestamp() as f64),
+            Self::Duration(d) => Ok(d.num_milliseconds() as f64 / 1000.0),
+        }
+    }
+
+    /// Order two values, reinterpreting them first. Dates and datetimes are
+    /// compared as points in time (a date being its midnight), durations as
+    /// lengths of time; everything else is compared as numbers. A literal that
+    /// is both a duration and a size, like `1m`, is a size next to a number.
+    pub fn compare(
+        self,
+        other: Self,
+        now: chrono::NaiveDateTime,
+    ) -> Result<Option<std::cmp::Ordering>, CastError> {
+        let as_size = |value: &Self| match value {
+            Self::String(s) => byte_unit::Byte::from_str(s)
+                .ok()
+                .map(|size| size.get_bytes() as f64),
+            _ => None,
+        };
+        let (lhs_size, rhs_size) = (as_size(&self), as_size(&other));
+        let not_a_number = CastError::IncompatibleCast {
+            from: "duration",
+            to: "number",
+        };
+        match (self.reinterpret(now), other.reinterpret(now)) {
+            (Self::DateTime(lhs), Self::DateTime(rhs)) => Ok(lhs.partial_cmp(&rhs)),
+            (Self::DateTime(lhs), Self::Date(rhs)) => {
+                Ok(lhs.partial_cmp(&rhs.and_time(chrono::NaiveTime::MIN)))
+            },
+            (Self::Date(lhs), Self::DateTime(rhs)) => {
+                Ok(lhs.and_time(chrono::NaiveTime::MIN).partial_cmp(&rhs))
+            },
+            (Self::Duration(lhs), Self::Duration(rhs)) => Ok(lhs.partial_cmp(&rhs)),
+            (Self::Duration(_), Self::Numeric(rhs)) => {
+                Ok(lhs_size.ok_or(not_a_number)?.partial_cmp(&rhs))
+            },
+            (Self::Numeric(lhs), Self::Duration(_)) => {
+                Ok(lhs.partial_cmp(&rhs_size.ok_or(not_a_number)?))
+            },
+            (lhs, rhs) => Ok(lhs.cast_to_number()?.partial_cmp(&rhs.cast_to_number()?)),
         }
     }
 
@@ -170,6 +389,14 @@ impl ExprResult {
                 from: "time",
                 to: "regex",
             }),
+            Self::DateTime(_) => Err(CastError::IncompatibleCast {
+                from: "datetime",
+                to: "regex",
+            }),
+            Self::Duration(_) => Err(CastError::IncompatibleCast {
+                from: "duration",
+                to: "regex",
+            }),
             Self::String(s) => regex::Regex::new(&s).map_err(CastError::RegexError),
         }
     }
@@ -195,7 +422,7 @@ impl ExprResult {
             None
         }
     }
-    pub fn parse_str(t: &str) -> Self {
+    pub fn parse_str(t: &str, now: chrono::NaiveDateTime) -> Self {
         // NOTE: use `lexical::parse` for these routines if you want to be even faster :)
         fn try_time(source: &str) -> Option<chrono::NaiveTime> {
             let first_colon = source.find(':')?;
@@ -213,15 +440,29 @@ impl ExprResult {
             let day = source[second_dash + 1..].parse().ok()?;
             chrono::NaiveDate::from_ymd_opt(year, month, day)
         }
+        fn try_datetime(source: &str) -> Option<chrono::NaiveDateTime> {
+            ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+                .iter()
+                .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(source, fmt).ok())
+        }
         // try to parse it in different ways
         if let Ok(num) = t.parse() {
             Self::Numeric(num)
         } else if let Ok(bool) = t.parse() {
             Self::Boolean(bool)
+        } else if let Some(datetime) = try_datetime(t) {
+            Self::DateTime(datetime)
+        } else if let Some(datetime) = parse_relative(t, now) {
+            Self::DateTime(datetime)
+        } else if let Some(duration) = parse_duration(t) {
+            Self::Duration(duration)
         } else if let Some(time) = try_time(t) {
             Self::Time(time)
         } else if let Some(date) = try_date(t) {
             Self::Date(date)
+        } else if let Ok(size) = byte_unit::Byte::from_str(t) {
+            // size literals, like `500k` or `1.5GiB`, become their bytes
+            Self::Numeric(size.get_bytes() as f64)
         } else {
             Self::String(t.into())
         }
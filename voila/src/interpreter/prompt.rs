@@ -0,0 +1,102 @@
+use super::Aborted;
+use crate::ast::{copy_destination, Function};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{RwLock, RwLockReadGuard};
+
+/// Asks the user on the terminal before executing destructive calls.
+///
+/// Calls run in parallel, so only one prompt is shown at a time: the rest of
+/// the calls wait for their turn, whether they need confirmation or not, so
+/// nothing runs nor gets printed over the prompt while the user is answering.
+#[derive(Debug, Default)]
+pub struct Prompt {
+    /// Written while asking, and read by the calls while they run;
+    /// `true` once the user answered `all`
+    all: RwLock<bool>,
+    /// Whether the user answered `quit`
+    quit: AtomicBool,
+}
+
+impl Prompt {
+    /// Whether the user asked to stop the run
+    pub fn quit(&self) -> bool {
+        self.quit.load(Ordering::SeqCst)
+    }
+
+    /// Ask the user whether to execute a call with its resolved arguments. Calls that
+    /// don't need confirmation are always accepted. An accepted call gets its turn,
+    /// which it keeps while it runs so that no prompt is shown meanwhile.
+    pub fn confirm(
+        &self,
+        function: Function,
+        args: &[String],
+    ) -> Result<Option<RwLockReadGuard<'_, bool>>, Aborted> {
+        if Self::needs_confirmation(function, args) && !self.ask(function, args)? {
+            return Ok(None);
+        }
+        Ok(Some(self.all.read().unwrap()))
+    }
+
+    fn ask(&self, function: Function, args: &[String]) -> Result<bool, Aborted> {
+        let mut all = self.all.write().unwrap();
+        // the user may have answered while this call was waiting its turn
+        if self.quit() {
+            return Ok(false);
+        }
+        if *all {
+            return Ok(true);
+        }
+
+        let stdout = io::stdout();
+        let _paused = stdout.lock();
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        loop {
+            eprint!(
+                "{function}({args})? [y]es/[n]o/[a]ll/[q]uit: ",
+                args = args.join(", ")
+            );
+            io::stderr().flush().unwrap_or(());
+
+            let mut answer = String::new();
+            // there's no way to keep asking without input, so take it as a quit
+            if input.read_line(&mut answer).unwrap_or(0) == 0 {
+                eprintln!();
+                return self.abort();
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                "a" | "all" => {
+                    *all = true;
+                    return Ok(true);
+                },
+                "q" | "quit" => return self.abort(),
+                _ => continue,
+            }
+        }
+    }
+
+    fn abort(&self) -> Result<bool, Aborted> {
+        self.quit.store(true, Ordering::SeqCst);
+        Err(Aborted)
+    }
+
+    /// `delete`, `move`, `shell` and `child` always ask, while `copy` and `create`
+    /// only do when they'd overwrite something
+    fn needs_confirmation(function: Function, args: &[String]) -> bool {
+        match function {
+            Function::Delete { .. }
+            | Function::Move { .. }
+            | Function::Shell { .. }
+            | Function::Child { .. } => true,
+            Function::Copy { .. } => {
+                copy_destination(Path::new(&args[0]), args[1].as_str().into()).exists()
+            },
+            Function::Create { .. } => Path::new(&args[0]).exists(),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use super::Journal;
+use ansi_term::Colour::Red;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A `rename` call waiting for the end of the run
+#[derive(Debug)]
+struct Pending {
+    source: PathBuf,
+    template: String,
+    modified: chrono::NaiveDateTime,
+}
+
+/// Renames are done all at once after every file has been processed, sorted by
+/// path, so counters in the templates are predictable and collisions between the
+/// new names can be spotted before touching anything.
+#[derive(Debug, Default)]
+pub struct Renames {
+    pending: Mutex<Vec<Pending>>,
+}
+
+impl Renames {
+    /// Queue the rename of `source` to the name given by `template`
+    pub fn queue(&self, source: &str, template: &str) -> io::Result<()> {
+        let modified =
+            chrono::DateTime::<chrono::Utc>::from(fs::metadata(source)?.modified()?).naive_utc();
+        // catch malformed templates on the call that has them
+        expand(template, 0, &modified)?;
+        self.pending.lock().unwrap().push(Pending {
+            source: source.into(),
+            template: template.into(),
+            modified,
+        });
+        Ok(())
+    }
+
+    /// Do all the queued renames, skipping the ones that collide with an existing
+    /// file or with each other. A file can take the name of another one that is renamed
+    /// too, which is then renamed first. On a dry run, a table with the changes is
+    /// printed instead.
+    pub fn apply(&self, journal: Option<&Journal>, dry_run: bool) -> io::Result<()> {
+        let mut pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+        pending.sort_by(|a, b| a.source.cmp(&b.source));
+
+        let mut plan = Vec::with_capacity(pending.len());
+        for (i, rename) in pending.iter().enumerate() {
+            let name = expand(&rename.template, i + 1, &rename.modified)?;
+            plan.push((rename.source.as_path(), rename.source.with_file_name(name)));
+        }
+        let collisions = collisions(&plan);
+
+        if dry_run {
+            print_preview(&plan, &collisions);
+            return Ok(());
+        }
+        let sources: HashMap<&Path, usize> = plan
+            .iter()
+            .enumerate()
+            .map(|(i, (source, _))| (*source, i))
+            .collect();
+        let mut done = vec![false; plan.len()];
+        for i in 0..plan.len() {
+            // the files in the way of this one are moved first
+            let mut chain = vec![i];
+            while let Some(&next) = sources.get(plan[*chain.last().unwrap()].1.as_path()) {
+                if done[next] || collisions[next] || chain.contains(&next) {
+                    break;
+                }
+                chain.push(next);
+            }
+            for i in chain.into_iter().rev() {
+                done[i] = true;
+                let (source, target) = (plan[i].0, plan[i].1.as_path());
+                if source == target {
+                    continue;
+                }
+                // a file in the way that couldn't be moved is still there
+                if collisions[i] || fs::symlink_metadata(target).is_ok() {
+                    eprintln!(
+                        "skipped `rename` of {source} into {target}: another file has that name",
+                        source = source.display(),
+                        target = target.display()
+                    );
+                    continue;
+                }
+                if let Err(e) = fs::rename(source, target) {
+                    eprintln!(
+                        "couldn't `rename` {source} into {target}: {e}",
+                        source = source.display(),
+                        target = target.display()
+                    );
+                    continue;
+                }
+                if let Some(journal) = journal {
+                    journal.record_rename(source, target)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which renames of `plan` collide: with each other, with a file that isn't renamed,
+/// or in a cycle (like swapping two names), which has no file to be moved first
+fn collisions(plan: &[(&Path, PathBuf)]) -> Vec<bool> {
+    let mut targets: HashMap<&Path, usize> = HashMap::new();
+    for (_, target) in plan {
+        *targets.entry(target).or_default() += 1;
+    }
+    let sources: HashMap<&Path, usize> = plan
+        .iter()
+        .enumerate()
+        .map(|(i, (source, _))| (*source, i))
+        .collect();
+    let mut collisions: Vec<bool> = plan
+        .iter()
+        .map(|(source, target)| source != target && targets[target.as_path()] > 1)
+        .collect();
+    let moves = |collisions: &[bool], i: usize| plan[i].0 != plan[i].1 && !collisions[i];
+    // following the files in the way of each other leads back to the first one
+    let in_cycle = |collisions: &[bool], i: usize| {
+        let mut next = i;
+        for _ in 0..plan.len() {
+            match sources.get(plan[next].1.as_path()) {
+                Some(&j) if moves(collisions, j) => next = j,
+                _ => return false,
+            }
+            if next == i {
+                return true;
+            }
+        }
+        false
+    };
+    // skipping a rename leaves its file in the way of others, so it goes on until none changes
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..plan.len() {
+            if !moves(&collisions, i) {
+                continue;
+            }
+            let target = plan[i].1.as_path();
+            let collides = match sources.get(target) {
+                Some(&j) => !moves(&collisions, j) || in_cycle(&collisions, i),
+                None => fs::symlink_metadata(target).is_ok(),
+            };
+            if collides {
+                collisions[i] = true;
+                changed = true;
+            }
+        }
+    }
+    collisions
+}
+
+fn print_preview(plan: &[(&Path, PathBuf)], collisions: &[bool]) {
+    let width = plan
+        .iter()
+        .map(|(source, _)| source.display().to_string().len())
+        .max()
+        .unwrap_or(0);
+    println!("{:width$}    new name", "old name");
+    for ((source, target), &collides) in plan.iter().zip(collisions) {
+        let new_name = target
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let row = format!("{:width$} -> {new_name}", source.display().to_string());
+        if collides {
+            println!(
+                "{}",
+                Red.bold().paint(row + " (collision, will be skipped)")
+            );
+        } else {
+            println!("{row}");
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Case {
+    Upper,
+    Lower,
+}
+
+/// Expand the placeholders of a rename template:
+/// - `%n`: counter, starting at 1 in path order. `%3n` pads it to 3 digits with zeros.
+/// - `%U` and `%L`: upper/lowercase everything until `%E` or the end of the template.
+/// - `%Y`, `%m`, `%d`, `%H`, `%M` and `%S`: year, month, day, hour, minute and second
+///   of the last modification of the file (`@lastChange`).
+/// - `%%`: a literal percent sign.
+fn expand(template: &str, counter: usize, modified: &chrono::NaiveDateTime) -> io::Result<String> {
+    let invalid = |msg: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid rename template `{template}`: {msg}"),
+        )
+    };
+    let push = |out: &mut String, text: &str, case: Option<Case>| match case {
+        Some(Case::Upper) => out.push_str(&text.to_uppercase()),
+        Some(Case::Lower) => out.push_str(&text.to_lowercase()),
+        None => out.push_str(text),
+    };
+
+    let mut out = String::new();
+    let mut case = None;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            push(&mut out, c.encode_utf8(&mut [0; 4]), case);
+            continue;
+        }
+        let mut width = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            width.push(digit);
+        }
+        match chars.next() {
+            Some('n') => {
+                let width = width.parse().unwrap_or(0);
+                out.push_str(&format!("{counter:0width$}"));
+            },
+            Some(other) if !width.is_empty() => {
+                return Err(invalid(format!(
+                    "only counters can be padded, not `%{other}`"
+                )))
+            },
+            Some('%') => out.push('%'),
+            Some('U') => case = Some(Case::Upper),
+            Some('L') => case = Some(Case::Lower),
+            Some('E') => case = None,
+            Some(spec @ ('Y' | 'm' | 'd' | 'H' | 'M' | 'S')) => push(
+                &mut out,
+                &modified.format(&format!("%{spec}")).to_string(),
+                case,
+            ),
+            Some(other) => return Err(invalid(format!("unknown placeholder `%{other}`"))),
+            None => return Err(invalid("it ends with an unfinished placeholder".into())),
+        }
+    }
+    if out.is_empty() || out.contains(std::path::is_separator) {
+        return Err(invalid(format!("`{out}` is not a valid file name")));
+    }
+    Ok(out)
+}
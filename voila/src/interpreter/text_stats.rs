@@ -0,0 +1,131 @@
+/// Counts over the contents of a file, taken as UTF-8 text. Bytes that aren't valid
+/// UTF-8 count as a single character each, like when they're shown.
+#[derive(Debug, Default)]
+pub struct TextStats {
+    /// Lines, counting the last one even if it doesn't end in a newline
+    pub lines: usize,
+    /// Runs of characters between whitespace
+    pub words: usize,
+    pub chars: usize,
+    /// Characters of the longest line, without its line break
+    pub longest_line: usize,
+    /// Characters of the line being read
+    line: usize,
+    in_word: bool,
+    /// The start of a character that was split between two blocks
+    pending: Vec<u8>,
+    /// Whether no byte is outside of ASCII
+    ascii: bool,
+    /// Whether every byte was valid UTF-8
+    utf8: bool,
+    /// Whether there was any NUL byte, which text doesn't have
+    nul: bool,
+    /// The first bytes of the file, to look for a byte order mark
+    start: Vec<u8>,
+}
+
+impl TextStats {
+    pub fn new() -> Self {
+        Self {
+            ascii: true,
+            utf8: true,
+            ..Default::default()
+        }
+    }
+
+    /// Count the next block of the file
+    pub fn update_block(&mut self, block: &[u8]) {
+        if self.start.len() < 2 {
+            let missing = (2 - self.start.len()).min(block.len());
+            self.start.extend_from_slice(&block[..missing]);
+        }
+        self.ascii &= block.is_ascii();
+        self.nul |= block.contains(&0);
+
+        let joined;
+        let mut bytes = if self.pending.is_empty() {
+            block
+        } else {
+            self.pending.extend_from_slice(block);
+            joined = std::mem::take(&mut self.pending);
+            &joined[..]
+        };
+        loop {
+            match std::str::from_utf8(bytes) {
+                Ok(text) => {
+                    text.chars().for_each(|c| self.count(c));
+                    break;
+                },
+                Err(e) => {
+                    let (valid, rest) = bytes.split_at(e.valid_up_to());
+                    // the bytes were checked just now
+                    std::str::from_utf8(valid)
+                        .unwrap()
+                        .chars()
+                        .for_each(|c| self.count(c));
+                    match e.error_len() {
+                        Some(len) => {
+                            self.utf8 = false;
+                            self.count(char::REPLACEMENT_CHARACTER);
+                            bytes = &rest[len..];
+                        },
+                        // the character may continue in the next block
+                        None => {
+                            self.pending = rest.to_vec();
+                            break;
+                        },
+                    }
+                },
+            }
+        }
+    }
+
+    fn count(&mut self, c: char) {
+        self.chars += 1;
+        if c == '\n' {
+            self.end_line();
+        } else if c != '\r' {
+            self.line += 1;
+        }
+        if c.is_whitespace() {
+            self.in_word = false;
+        } else if !self.in_word {
+            self.in_word = true;
+            self.words += 1;
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.lines += 1;
+        self.longest_line = self.longest_line.max(self.line);
+        self.line = 0;
+    }
+
+    /// Count what's left once the whole file was read
+    pub fn finish(mut self) -> Self {
+        if !self.pending.is_empty() {
+            self.utf8 = false;
+            self.pending.clear();
+            self.count(char::REPLACEMENT_CHARACTER);
+        }
+        if self.line > 0 {
+            self.end_line();
+        }
+        self
+    }
+
+    /// The encoding the file seems to have: `ascii`, `utf-8`, `utf-16le` or `utf-16be`
+    /// (told by their byte order marks), `binary` if it has NUL bytes, `unknown`,
+    /// or `empty` if there are no contents
+    pub fn encoding(&self) -> &'static str {
+        match self.start.as_slice() {
+            [] => "empty",
+            [0xff, 0xfe, ..] => "utf-16le",
+            [0xfe, 0xff, ..] => "utf-16be",
+            _ if self.nul => "binary",
+            _ if self.ascii => "ascii",
+            _ if self.utf8 => "utf-8",
+            _ => "unknown",
+        }
+    }
+}
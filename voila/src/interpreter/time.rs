@@ -0,0 +1,63 @@
+use chrono::{Duration, NaiveDateTime};
+
+/// Seconds in each duration unit, biggest first
+const UNITS: [(char, i64); 5] = [
+    ('w', 604_800),
+    ('d', 86_400),
+    ('h', 3_600),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Parse a duration literal like `30d`, `1.5h` or `1w 2d`. The units are `s`, `m`,
+/// `h`, `d` and `w` (seconds, minutes, hours, days and weeks). Durations too long
+/// to be represented aren't durations.
+pub fn parse_duration(source: &str) -> Option<Duration> {
+    let mut total = Duration::zero();
+    let mut parts = source.split_whitespace().peekable();
+    parts.peek()?;
+    for part in parts {
+        let unit = part.chars().last()?;
+        let (_, seconds) = UNITS.iter().find(|(u, _)| *u == unit)?;
+        let amount: f64 = part[..part.len() - 1].parse().ok()?;
+        let milliseconds = amount * *seconds as f64 * 1000.0;
+        if !milliseconds.is_finite() || milliseconds.abs() >= i64::MAX as f64 {
+            return None;
+        }
+        total = total.checked_add(&Duration::milliseconds(milliseconds as i64))?;
+    }
+    Some(total)
+}
+
+/// Parse an instant relative to the start of the run, which is `now`: `now`,
+/// `now - 30d`, `now + 2h`...
+pub fn parse_relative(source: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let offset = source.trim().strip_prefix("now")?.trim_start();
+    if offset.is_empty() {
+        return Some(now);
+    }
+    let (sign, duration) = offset.split_at(1);
+    let duration = parse_duration(duration)?;
+    match sign {
+        "+" => now.checked_add_signed(duration),
+        "-" => now.checked_sub_signed(duration),
+        _ => None,
+    }
+}
+
+/// Present a duration as the literals that would produce it, like `1w 2d 3h`
+pub fn format_duration(duration: Duration) -> String {
+    let sign = if duration < Duration::zero() { "-" } else { "" };
+    let mut seconds = duration.num_seconds().abs();
+    if seconds == 0 {
+        return "0s".into();
+    }
+    let mut parts = Vec::new();
+    for (unit, length) in UNITS {
+        if seconds >= length {
+            parts.push(format!("{sign}{}{unit}", seconds / length));
+            seconds %= length;
+        }
+    }
+    parts.join(" ")
+}
@@ -3,14 +3,22 @@ use std::fmt;
 
 #[derive(Debug, Logos, Clone, Copy, PartialEq, Eq)]
 pub enum Token {
-    #[regex(r"@[A-Za-z0-9]+(?:=[A-Za-z0-9]+)?")]
+    // specifiers are letters and digits, or a range like `10..20`, so `@sum=md5.txt` is
+    // still a sum followed by `.txt`. Any other one is quoted, like `@grep='^\s*fn '`.
+    #[regex(r#"@[A-Za-z0-9]+(?:=(?:[A-Za-z0-9]+(?:\.\.[A-Za-z0-9]*)?|\.\.[A-Za-z0-9]+|"([^"\\]|\\.)*"|'([^'\\]|\\.)*'))?"#)]
     Variable,
 
-    #[regex(r"[^@{}(),\s;]+")]
+    // quotes start quoted strings instead, but words can have them, like `it's`
+    #[regex(r#"[^@{}(),\s;"'][^@{}(),\s;]*"#)]
     Identifier,
 
-    #[regex(r#"["']([^'"]+)["']"#)]
-    RawIdentifier,
+    // an at that isn't the start of a variable
+    #[token("@@")]
+    EscapedAt,
+
+    // the same quote ends it, unless it's escaped with a backslash
+    #[regex(r#""([^"\\]|\\.)*"|'([^'\\]|\\.)*'"#)]
+    QuotedString,
 
     #[token(",")]
     Comma,
@@ -78,7 +86,8 @@ impl fmt::Display for Token {
             Self::LEq => write!(f, "operator `<=`"),
             Self::LThan => write!(f, "operator `<`"),
             Self::Identifier => write!(f, "identifier"),
-            Self::RawIdentifier => write!(f, "raw identifier"),
+            Self::EscapedAt => write!(f, "escaped at `@@`"),
+            Self::QuotedString => write!(f, "quoted string"),
             Self::LogicAnd => write!(f, "operator `&&`"),
             Self::LogicOr => write!(f, "operator `||`"),
             Self::Match => write!(f, "match operator `~=`"),
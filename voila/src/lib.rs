@@ -1,5 +1,5 @@
 #![forbid(unsafe_code)] // unsafe code makes ferris get nervous
-#![feature(once_cell)]
+#![feature(once_cell_try)]
 #![feature(decl_macro)]
 #![feature(never_type)]
 #![allow(dead_code)]
@@ -16,22 +16,55 @@ pub mod macros;
 mod parser;
 mod safety;
 
-pub fn run(source: String, dir: std::path::PathBuf, recursive: bool) -> Result<(), Box<dyn Error>> {
-    exec(get_checked_ast(&source)?, dir, recursive)?;
+pub use ast::Overwrite;
+pub use interpreter::Options;
+
+pub fn run(
+    source: String,
+    dir: std::path::PathBuf,
+    recursive: bool,
+    options: Options,
+) -> Result<(), Box<dyn Error>> {
+    let (ast, warnings) = get_checked_ast(&source, &options.defines, options.lenient)?;
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
+    exec(ast, dir, recursive, options)?;
     Ok(())
 }
 
-pub fn get_checked_ast(source: &str) -> Result<ast::Script, Box<dyn Error>> {
-    let ast = ast::parse_script(source)?;
+/// Parse the script, bind the values given for it and check that it's safe to run.
+/// Unknown variables are errors unless it's `lenient`, when they are warnings instead,
+/// which are returned for the caller to show.
+pub fn get_checked_ast<'source>(
+    source: &'source str,
+    defines: &[(String, String)],
+    lenient: bool,
+) -> Result<(ast::Script<'source>, Vec<impl std::fmt::Display>), Box<dyn Error>> {
+    let (mut ast, warnings) = ast::parse_script(source, defines, lenient)?;
+    for (name, value) in defines {
+        ast.define(name, value);
+    }
     ast.ub_checks(source)?;
-    Ok(ast)
+    Ok((ast, warnings))
 }
 
 pub fn exec(
     ast: ast::Script,
     dir: std::path::PathBuf,
     recursive: bool,
+    options: Options,
 ) -> Result<(), Box<dyn Error>> {
-    interpreter::run(ast, dir, recursive)?;
+    interpreter::run(ast, dir, recursive, options)?;
     Ok(())
 }
+
+/// Remove the stale entries of the sum cache, returning how many were removed
+pub fn prune_hash_cache() -> Result<usize, Box<dyn Error>> {
+    interpreter::prune_hash_cache()
+}
+
+/// Revert the changes recorded in a journal by a previous run
+pub fn undo(journal: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    interpreter::undo(journal)
+}
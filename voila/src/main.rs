@@ -26,10 +26,11 @@ For more information see the README.
 ------------------------------  VOILA EXECUTION STARTED  ------------------------------"#,
         ver = env!("CARGO_PKG_VERSION")
     );
-    let target = if cli_args.compile {
-        runtime::compile
-    } else {
-        runtime::interpret
+    let target = match cli_args.command {
+        Some(cli::Command::Undo { .. }) => runtime::undo,
+        Some(cli::Command::PruneCache) => runtime::prune_cache,
+        None if cli_args.compile => runtime::compile,
+        None => runtime::interpret,
     };
 
     if let Err(e) = target(cli_args) {
@@ -1,10 +1,11 @@
-use super::error::SourceError;
+use super::error::{SourceError, SourceWarning};
 pub use super::lexer::Token;
 use std::error::Error;
 use std::fmt;
 use std::ops::Range;
 
 pub type ParseError = SourceError<ParseErrorKind, ContextLevel>;
+pub type ParseWarning = SourceWarning<ParseErrorKind, ContextLevel>;
 pub type ParseRes<T> = Result<T, ParseError>;
 
 /// An error that occured during parsing
@@ -18,7 +19,19 @@ pub enum ParseErrorKind {
     UnexpectedEOF {
         wanted: Option<WantedSpec<Token>>,
     },
-    UnknownVariable,
+    UnknownVariable {
+        /// The known variable with the closest name, if any is close enough
+        suggestion: Option<String>,
+    },
+    /// An unknown variable that is taken as text, without strict mode
+    TextVariable {
+        suggestion: Option<String>,
+    },
+    /// A variable of an optional feature that voila was built without
+    MissingFeature {
+        variable: String,
+        feature: &'static str,
+    },
     InvalidSpecifier {
         variable: &'static str,
         options: &'static [&'static str],
@@ -29,6 +42,9 @@ pub enum ParseErrorKind {
         options: &'static [&'static str],
     },
     UnknownFunction,
+    InvalidBinding,
+    BoundLookup(String),
+    NoOverwritePolicy(crate::ast::Function),
 }
 
 impl ParseErrorKind {
@@ -95,6 +111,12 @@ pub struct Parser<'source> {
     lexer: logos::Lexer<'source, Token>,
     current: Option<(Token, Range<usize>)>,
     current_context: ContextLevel,
+    /// The names of the bindings known so far
+    bindings: Vec<String>,
+    /// Whether unknown variables are errors, instead of text
+    strict: bool,
+    /// Problems found that don't stop the parsing
+    warnings: Vec<ParseWarning>,
 }
 
 /// The context in which the parser is in.
@@ -106,6 +128,7 @@ pub enum ContextLevel {
     Cycle,
     Call,
     InterpSeq,
+    Binding,
 }
 
 impl Default for ContextLevel {
@@ -124,6 +147,7 @@ impl fmt::Display for ContextLevel {
             Self::Cycle => "cycle",
             Self::Call => "function call",
             Self::InterpSeq => "interpolation sequence",
+            Self::Binding => "binding",
         })
     }
 }
@@ -137,8 +161,26 @@ impl<'source> Parser<'source> {
             input,
             current: None,
             current_context: ContextLevel::default(),
+            bindings: Vec::new(),
+            strict: true,
+            warnings: Vec::new(),
         }
     }
+    /// Take unknown variables as text, warning about them, instead of failing
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.strict = !lenient;
+        self
+    }
+    /// Make a binding known, so variables with its name can be used from now on
+    pub fn bind(&mut self, name: &str) {
+        self.bindings.push(name.to_string())
+    }
+    pub fn bindings(&self) -> &[String] {
+        &self.bindings
+    }
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
     /// Using the current lexer span and the source, generate a [SourceError]
     /// with a [ParseErrorKind]
     pub fn error(&self, kind: ParseErrorKind) -> ParseError {
@@ -146,6 +188,17 @@ impl<'source> Parser<'source> {
             .with_source(self.lexer.span(), self.input)
             .with_context(self.current_context)
     }
+    /// Keep a warning about the source at `span`, which doesn't stop the parsing
+    pub fn warn(&mut self, kind: ParseErrorKind, span: Range<usize>) {
+        let warning = ParseError::new(kind)
+            .with_source(span, self.input)
+            .with_context(self.current_context);
+        self.warnings.push(SourceWarning(warning));
+    }
+    /// The warnings kept while parsing
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
     /// Get the current token, or spit out a lex error
     pub fn current_token(&mut self) -> ParseRes<Option<Token>> {
         if let Some((tok, _)) = self.current {
@@ -316,10 +369,27 @@ impl fmt::Display for ParseErrorKind {
                 wanted: Some(ref wanted),
             } => write!(f, "unexpected EOF, wanted {}", wanted),
             Self::UnexpectedEOF { wanted: None } => write!(f, "unexpected EOF"),
-            Self::UnknownVariable => {
+            Self::UnknownVariable { suggestion } => {
+                if let Some(suggestion) = suggestion {
+                    writeln!(f, "Unknown variable name, did you mean `@{suggestion}`?")?;
+                } else {
+                    writeln!(f, "Unknown variable name")?;
+                }
                 // TODO: Update link when docs change!!!
-                write!(f, "Unknown variable name\nthe list of supported variables is at the docs: https://github.com/Alonely0/Voila")
+                write!(f, "use `@@` for an at that isn't a variable, or see the list of supported variables at the docs: https://github.com/Alonely0/Voila")
             },
+            Self::TextVariable { suggestion } => {
+                if let Some(suggestion) = suggestion {
+                    writeln!(f, "Unknown variable name, taken as text. Did you mean `@{suggestion}`?")?;
+                } else {
+                    writeln!(f, "Unknown variable name, taken as text")?;
+                }
+                write!(f, "use `@@` for an at that isn't a variable")
+            },
+            Self::MissingFeature { variable, feature } => write!(
+                f,
+                "The variable `@{variable}` needs voila to be built with the `{feature}` feature (`cargo install voila --features {feature}`)"
+            ),
             Self::InvalidSpecifier { variable, options } => {
                 write!(
                     f,
@@ -339,6 +409,9 @@ impl fmt::Display for ParseErrorKind {
             }
             // TODO: update link when docs change!
             Self::UnknownFunction => write!(f, "Unknown function name\nthe list of supported functions is at the docs: https://github.com/Alonely0/Voila"),
+            Self::InvalidBinding => write!(f, "Bindings are written as `let @name = value;`, with spaces around `=`"),
+            Self::BoundLookup(name) => write!(f, "`@{name}` is already a variable of the files, so it can't be bound to something else"),
+            Self::NoOverwritePolicy(function) => write!(f, "`{function}` doesn't write into a destination, so it can't have an overwrite policy (only copy, move, create, gzc and gzd can)"),
         }
     }
 }
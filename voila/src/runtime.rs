@@ -1,18 +1,57 @@
 pub use std::process::exit;
 
+/// The settings of the run given in the command line
+fn options(args: &crate::cli::Cli) -> super::Options {
+    super::Options {
+        journal: args.journal.clone(),
+        interactive: args.interactive,
+        overwrite: args.overwrite.unwrap_or_default(),
+        dry_run: args.dry_run,
+        hash_cache: !args.no_hash_cache,
+        max_content: Some(args.max_content),
+        defines: args.define.clone(),
+        lenient: args.lenient,
+    }
+}
+
 pub fn interpret(args: crate::cli::Cli) -> Result<(), String> {
-    super::run(args.source, args.dir, args.recursive).map_err(|e| format!("{e}"))
+    let options = options(&args);
+    super::run(
+        args.source.unwrap(),
+        args.dir.unwrap(),
+        args.recursive,
+        options,
+    )
+    .map_err(|e| format!("{e}"))
+}
+
+pub fn undo(args: crate::cli::Cli) -> Result<(), String> {
+    match args.command {
+        Some(crate::cli::Command::Undo { journal }) => {
+            super::undo(&journal).map_err(|e| format!("{e}"))
+        },
+        _ => unreachable!(),
+    }
+}
+
+pub fn prune_cache(_: crate::cli::Cli) -> Result<(), String> {
+    let removed = super::prune_hash_cache().map_err(|e| format!("{e}"))?;
+    println!("removed {removed} stale entries from the sum cache");
+    Ok(())
 }
 
 pub fn compile(args: crate::cli::Cli) -> Result<(), String> {
+    let (ast, warnings) =
+        voila::get_checked_ast(args.source.as_ref().unwrap(), &args.define, args.lenient)
+            .map_err(|e| format!("{e}"))?;
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
     crate::compiler::compile([
-        &format!(
-            "{:?}",
-            bincode::serialize(&voila::get_checked_ast(&args.source).map_err(|e| format!("{e}"))?)
-                .unwrap()
-        ),
-        args.dir.as_os_str().to_str().unwrap(),
+        &format!("{:?}", bincode::serialize(&ast).unwrap()),
+        args.dir.as_ref().unwrap().as_os_str().to_str().unwrap(),
         &format!("{r}", r = args.recursive),
+        &format!("{:?}", bincode::serialize(&options(&args)).unwrap()),
     ])
     .map_err(|e| e.to_string())
 }
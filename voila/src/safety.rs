@@ -160,18 +160,75 @@ impl IO {
     const ACCESS_VARS: &'static [&'static str] = &[
         #[cfg(unix)]
         "ownerID",
+        #[cfg(unix)]
+        "owner",
+        #[cfg(unix)]
+        "groupID",
+        #[cfg(unix)]
+        "group",
+        "mode",
+        "executable",
+        #[cfg(unix)]
+        "setuid",
+        #[cfg(unix)]
+        "inode",
+        #[cfg(unix)]
+        "nlink",
+        #[cfg(unix)]
+        "blocks",
         "empty",
         "readonly",
         "elf",
         "txt",
+        "mime",
+        "filetype",
         "content",
+        "linecount",
+        "wordcount",
+        "charcount",
+        "longestline",
+        "encoding",
+        "entropy",
+        "nullbytes",
         "line",
+        "lines",
+        "head",
+        "tail",
+        "grep",
+        "grepcount",
         "hidden",
         "size",
         "sum",
         "creation",
         "lastChange",
         "lastAccess",
+        #[cfg(unix)]
+        "ctime",
+        "age",
+        "sumfile",
+        "verify",
+        "duplicate",
+        "dupcount",
+        "original",
+        "width",
+        "height",
+        "imgformat",
+        "exif",
+        "exifyear",
+        "exifmonth",
+        "exifday",
+        #[cfg(feature = "media")]
+        "tag",
+        #[cfg(feature = "media")]
+        "duration",
+        #[cfg(feature = "media")]
+        "pages",
+        #[cfg(feature = "git")]
+        "gitstatus",
+        #[cfg(feature = "git")]
+        "gitauthor",
+        #[cfg(feature = "git")]
+        "gitlastcommit",
     ];
     /// Search matches through all operations types
     /// of an [IO] and returns vectors representing matches
@@ -299,7 +356,7 @@ impl<'source> crate::ast::Script<'source> {
                             call.function_kind,
                             call.arguments
                                 .iter()
-                                .map(|x| x.sequence.clone())
+                                .map(|x| expand_bindings(&x.sequence, &self.bindings))
                                 .collect::<Vec<Vec<StrComponent>>>(),
                             call.span().into(),
                         ),
@@ -337,7 +394,10 @@ impl<'source> crate::ast::Script<'source> {
             Shell { safe: true } | Child { safe: true } => {
                 modified = Some(args);
             },
-            Delete { safe: true } => {
+            Delete { safe: true }
+            | Rename { safe: true }
+            | Chmod { safe: true }
+            | Touch { safe: true } => {
                 modified = Some(vec![args.get(0).unwrap_or(&Vec::new()).to_vec()])
             },
             Move { safe: true } | GzipDecompress { safe: true } => {
@@ -348,6 +408,14 @@ impl<'source> crate::ast::Script<'source> {
                 accessed = Some(vec![args.get(0).unwrap_or(&Vec::new()).to_vec()]);
                 created = Some(vec![args.get(1).unwrap_or(&Vec::new()).to_vec()]);
             },
+            // the file that is read is the current one, the manifest is written when the run ends
+            Manifest { safe: true } => {
+                accessed = Some(vec![vec![StrComponent::Lookup(Lookup::Path)]]);
+            },
+            #[cfg(unix)]
+            Chown { safe: true } => {
+                modified = Some(vec![args.first().unwrap_or(&Vec::new()).to_vec()])
+            },
             _ => unreachable!(),
         }
         IO::new(created, accessed, modified, metadata)